@@ -0,0 +1,75 @@
+//! Finds the spoofer binary that the launcher embeds, so a missing one is a clear build error
+//! rather than a launcher that can't start anything, and works out the version string that
+//! `--version` prints.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(spoofer_bin_missing)");
+    println!("cargo::rerun-if-changed=build.rs");
+
+    set_version();
+
+    // OUT_DIR is target/<profile>/build/<package>-<hash>/out, and the binaries sit in
+    // target/<profile>, with a target triple in between when cross compiling
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo should set OUT_DIR"));
+    let Some(profile_dir) = out_dir.ancestors().nth(3) else {
+        println!("cargo::rustc-cfg=spoofer_bin_missing");
+        return;
+    };
+    let exe_suffix = if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    let spoofer = profile_dir.join(format!("spoofer{exe_suffix}"));
+    println!("cargo::rerun-if-changed={}", spoofer.display());
+
+    match fs::metadata(&spoofer) {
+        Ok(meta) if meta.len() > 0 => {
+            println!(
+                "cargo::rustc-env=DOPPELGAMER_SPOOFER_BIN={}",
+                spoofer.display()
+            );
+            let source = fs::metadata("src/bin/spoofer.rs").and_then(|m| m.modified());
+            if let (Ok(built), Ok(source)) = (meta.modified(), source)
+                && built < source
+            {
+                println!(
+                    "cargo::warning=the spoofer at {} is older than its source, run `cargo build \
+                     --bin spoofer` again to embed the latest one",
+                    spoofer.display()
+                );
+            }
+        }
+        // this can't fail the build here, since this script also runs when building the
+        // spoofer itself. the launcher turns the cfg into a compile error instead
+        _ => println!("cargo::rustc-cfg=spoofer_bin_missing"),
+    }
+}
+
+/// The package version, followed by the commit it was built from when that can be found out.
+/// Source tarballs have no git history, and then the version alone has to do.
+fn set_version() {
+    let version = env::var("CARGO_PKG_VERSION").expect("cargo should set CARGO_PKG_VERSION");
+    // a new commit or checkout moves one of these, and watching paths that don't exist would
+    // rerun this script on every build
+    for watched in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if Path::new(watched).exists() {
+            println!("cargo::rerun-if-changed={watched}");
+        }
+    }
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty());
+    match commit {
+        Some(commit) => println!("cargo::rustc-env=DOPPELGAMER_VERSION={version} ({commit})"),
+        None => println!("cargo::rustc-env=DOPPELGAMER_VERSION={version}"),
+    }
+}
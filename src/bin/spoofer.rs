@@ -1,133 +1,1431 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::{process, time::Duration};
 
-use jiff::{Span, Unit, Zoned};
+use anyhow::Context as _;
+use clap::Parser as _;
+use doppelgamer::{Alert, Alerts, CountdownStyle, SpooferSkin, ThemeChoice};
+use jiff::{RoundMode, Span, SpanRelativeTo, SpanRound, Unit, Zoned};
+use tracing::{info, warn};
 
 const ICON: &[u8] = include_bytes!("../../resources/snek.ico");
+const EXTEND_MINUTES: i64 = 15;
+/// How often tray menu clicks are picked up, kept apart from `--refresh` so that a slow tick
+/// doesn't leave the menu doing nothing for a while.
+const TRAY_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Woken by `stop` on the status pipe, which `doppelgamer stop` and replacing a spoofer send,
+/// to end the session the same way "Stop now" does.
+static STOP: std::sync::LazyLock<tokio::sync::Notify> =
+    std::sync::LazyLock::new(tokio::sync::Notify::new);
+
+/// Pretends to be a game for a while, then exits.
+#[derive(clap::Parser)]
+#[command(version = env!("DOPPELGAMER_VERSION"))]
+struct Args {
+    game_name: String,
+    /// Session length, like 30, 45m or 1h. Anything over --max-minutes is cut down to it.
+    #[arg(value_parser = doppelgamer::parse_uncapped_duration)]
+    duration: Span,
+    /// Longest session to run for, including after extending it. The launcher never asks for
+    /// more than this by default, so it only matters when starting the spoofer by hand.
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        default_value_t = doppelgamer::MAX_DURATION_MINUTES,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    max_minutes: u32,
+    /// Window icon to use instead of the snake.
+    #[arg(long, value_name = "PATH")]
+    icon: Option<PathBuf>,
+    /// Hide in the system tray instead of closing or minimizing.
+    #[arg(long)]
+    tray: bool,
+    /// Keep the window above other windows.
+    #[arg(long)]
+    always_on_top: bool,
+    /// Show a desktop notification when the time is up.
+    #[arg(long)]
+    notify: bool,
+    /// Notify at these points, as percentages gone by or time left, like 50,1m.
+    #[arg(long, value_name = "ALERTS", default_value_t, value_parser = doppelgamer::parse_alerts)]
+    alerts: Alerts,
+    /// Flash on the taskbar once this many seconds are left, or never if 0.
+    #[arg(long, value_name = "SECS", default_value_t = doppelgamer::DEFAULT_FLASH_SECS)]
+    flash_secs: u32,
+    /// Don't open a window, just wait until the time is up. Ctrl+C exits early.
+    #[arg(long)]
+    headless: bool,
+    /// Start over when the time is up instead of exiting.
+    #[arg(long = "loop")]
+    loop_session: bool,
+    /// Carry on with the session this game's spoofer was in the middle of when it died, if there
+    /// is one, instead of starting a new one.
+    #[arg(long)]
+    resume: bool,
+    /// Keep the computer from going to sleep until exiting. Windows only.
+    #[arg(long)]
+    keep_awake: bool,
+    /// Let the window be resized.
+    #[arg(long)]
+    resizable: bool,
+    /// Minimize the window as soon as it opens, so it doesn't sit on top of everything else.
+    #[arg(long)]
+    minimized: bool,
+    /// Initial size of the window, such as 480x240.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = doppelgamer::parse_window_size)]
+    window_size: Option<(u16, u16)>,
+    /// Draw the window and everything in it this much bigger, such as 1.5. The window size is
+    /// scaled too.
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = doppelgamer::parse_scale)]
+    scale: f32,
+    /// Light or dark window, or whatever the system uses.
+    #[arg(long, value_enum, default_value_t)]
+    theme: ThemeChoice,
+    /// Show time remaining and elapsed as a clock, like 01:02:03, or spelled out in units.
+    #[arg(long, value_enum, default_value_t)]
+    countdown: CountdownStyle,
+    /// How the window looks. Every skin counts down and exits the same way.
+    #[arg(long, value_enum, default_value_t)]
+    skin: SpooferSkin,
+    /// Wake up every this many seconds instead of every second. The countdown is shown in
+    /// steps of it.
+    #[arg(
+        long = "refresh",
+        value_name = "SECS",
+        default_value_t = doppelgamer::DEFAULT_REFRESH_SECS,
+        value_parser = doppelgamer::parse_refresh_secs
+    )]
+    refresh_secs: u32,
+    /// strftime format for the exit time, such as "%I:%M %p".
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = doppelgamer::DEFAULT_TIME_FORMAT,
+        value_parser = doppelgamer::parse_time_format
+    )]
+    time_format: String,
+    /// A line to show under the game, like "Grinding raids".
+    #[arg(long, value_name = "TEXT")]
+    status: Option<String>,
+    /// Show the session as Discord Rich Presence too, using this Discord application id.
+    #[arg(long, value_name = "APP_ID")]
+    rpc: Option<String>,
+    /// Image for the Rich Presence, as an asset key of the Discord application or an image url.
+    #[arg(long, value_name = "KEY", requires = "rpc")]
+    rpc_image: Option<String>,
+    /// How long to wait after the duration is up before exiting.
+    #[arg(long = "grace", value_name = "SECS")]
+    grace_secs: Option<u32>,
+    /// Keep the window position here instead of the usual config directory.
+    #[arg(long, env = doppelgamer::CONFIG_DIR_ENV, value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+    /// Write the log here instead of the usual data directory.
+    #[arg(long, env = doppelgamer::DATA_DIR_ENV, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+enum TrayAction {
+    Show,
+    Quit,
+}
+
+/// Keeps windows from going to sleep until dropped, so the session isn't cut short.
+#[cfg(windows)]
+struct KeepAwake;
+
+#[cfg(windows)]
+impl KeepAwake {
+    fn new() -> anyhow::Result<Self> {
+        use windows_sys::Win32::System::Power::{
+            ES_CONTINUOUS, ES_SYSTEM_REQUIRED, SetThreadExecutionState,
+        };
+        // SAFETY: this only changes the execution state flags of the calling thread
+        let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+        anyhow::ensure!(previous != 0, "SetThreadExecutionState failed");
+        Ok(Self)
+    }
+}
+
+#[cfg(windows)]
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        use windows_sys::Win32::System::Power::{ES_CONTINUOUS, SetThreadExecutionState};
+        // SAFETY: as above, and clearing the flags is always allowed
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+    }
+}
+
+/// Shows the session in Discord through Rich Presence for as long as it's held, clearing it
+/// again when dropped.
+struct Presence {
+    client: discord_rich_presence::DiscordIpcClient,
+    game_name: String,
+    status: Option<String>,
+    image: Option<String>,
+}
+
+impl Presence {
+    /// Connects with `--rpc`, if it was given. Discord not running is normal enough that failing
+    /// to connect isn't worth a warning.
+    fn connect(args: &Args) -> Option<Self> {
+        let app_id = args.rpc.as_deref()?;
+        Self::new(app_id, args)
+            .inspect(|_| info!("Showing the session in Discord"))
+            .inspect_err(|e| tracing::debug!("No Discord Rich Presence: {e:#}"))
+            .ok()
+    }
+
+    fn new(app_id: &str, args: &Args) -> anyhow::Result<Self> {
+        use discord_rich_presence::DiscordIpc as _;
+        // its errors aren't Send, so they can only come along as text
+        let mut client = discord_rich_presence::DiscordIpcClient::new(app_id)
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        client.connect().map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(Self {
+            client,
+            game_name: args.game_name.clone(),
+            status: args
+                .status
+                .clone()
+                .filter(|status| !status.trim().is_empty()),
+            image: args.rpc_image.clone(),
+        })
+    }
+
+    /// Shows the session as having started at `start`. Called again whenever that moves, after
+    /// a pause or when a loop starts over, so Discord's elapsed time matches the spoofer's.
+    fn show(&mut self, start: &Zoned) {
+        use discord_rich_presence::{DiscordIpc as _, activity};
+        let mut activity = activity::Activity::new()
+            .details(&self.game_name)
+            .timestamps(activity::Timestamps::new().start(start.timestamp().as_second()));
+        if let Some(status) = &self.status {
+            activity = activity.state(status);
+        }
+        if let Some(image) = &self.image {
+            activity = activity.assets(
+                activity::Assets::new()
+                    .large_image(image)
+                    .large_text(&self.game_name),
+            );
+        }
+        if let Err(e) = self.client.set_activity(activity) {
+            tracing::debug!("Failed to update Discord Rich Presence: {e}");
+        }
+    }
+}
+
+impl Drop for Presence {
+    fn drop(&mut self) {
+        use discord_rich_presence::DiscordIpc as _;
+        let _ = self.client.clear_activity();
+        let _ = self.client.close();
+    }
+}
+
+/// Where the spoofer window was last left, brought back on the next launch.
+#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+struct WindowPosition {
+    x: f32,
+    y: f32,
+}
+
+fn window_position_path() -> anyhow::Result<PathBuf> {
+    let mut path = doppelgamer::config_dir()?;
+    std::fs::create_dir_all(&path)?;
+    path.push("spoofer-window.json");
+    Ok(path)
+}
+
+/// The saved position, unless the screen it was on has gone since.
+fn load_window_position() -> Option<WindowPosition> {
+    let data = std::fs::read_to_string(window_position_path().ok()?).ok()?;
+    let position: WindowPosition = serde_json::from_str(&data).ok()?;
+    on_a_monitor(position).then_some(position)
+}
+
+fn save_window_position(position: WindowPosition) -> anyhow::Result<()> {
+    let path = window_position_path()?;
+    doppelgamer::write_atomically(&path, |file| Ok(serde_json::to_writer(file, &position)?))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Written whenever the quit time moves and removed on a clean exit, so a spoofer that crashed
+/// or was killed by logging off can be started again with `--resume` and still end on time.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct SavedSession {
+    game: String,
+    duration: Span,
+    start_time: jiff::Timestamp,
+    quit_time: jiff::Timestamp,
+}
+
+/// Next to the running exe, which is the game's own exe path.
+fn session_path() -> anyhow::Result<PathBuf> {
+    Ok(doppelgamer::spoofers::session_path(
+        &std::env::current_exe()?
+    ))
+}
+
+fn load_session() -> Option<SavedSession> {
+    let data = std::fs::read_to_string(session_path().ok()?).ok()?;
+    serde_json::from_str(&data)
+        .inspect_err(|e| warn!("Ignoring corrupt saved session: {e}"))
+        .ok()
+}
+
+fn save_session(session: &SavedSession) -> anyhow::Result<()> {
+    let path = session_path()?;
+    doppelgamer::write_atomically(&path, |file| Ok(serde_json::to_writer(file, session)?))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn clear_session() {
+    match session_path().map(std::fs::remove_file) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Ok(Err(e)) => warn!("Failed to remove the saved session: {e}"),
+        Err(e) => warn!("Failed to find the saved session: {e:#}"),
+    }
+}
+
+/// Whether a window placed here would show up on one of the screens. An unplugged monitor
+/// would leave it somewhere nobody can see, and so does minimizing, which parks the window
+/// far off screen.
+///
+/// iced positions are in logical pixels while monitors are laid out in physical ones, so each
+/// monitor is scaled down by its own DPI before checking.
+#[cfg(windows)]
+fn on_a_monitor(position: WindowPosition) -> bool {
+    use windows_sys::Win32::Foundation::{LPARAM, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+    use windows_sys::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    #[allow(clippy::cast_precision_loss)]
+    unsafe extern "system" fn check(
+        monitor: HMONITOR,
+        _dc: HDC,
+        rect: *mut RECT,
+        data: LPARAM,
+    ) -> windows_sys::core::BOOL {
+        // SAFETY: data is the pointer passed to EnumDisplayMonitors below, which outlives the
+        // enumeration, and rect is valid for the length of the callback
+        let (position, found) = unsafe { &mut *(data as *mut (WindowPosition, bool)) };
+        let rect = unsafe { *rect };
+        let (mut dpi_x, mut dpi_y) = (96, 96);
+        // SAFETY: the monitor comes from the enumeration, and on failure the dpi stays at 96
+        unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &raw mut dpi_x, &raw mut dpi_y) };
+        let scale = dpi_x as f32 / 96.0;
+        let x = rect.left as f32 / scale..rect.right as f32 / scale;
+        let y = rect.top as f32 / scale..rect.bottom as f32 / scale;
+        *found = x.contains(&position.x) && y.contains(&position.y);
+        // returning false stops the enumeration once a monitor has been found
+        (!*found).into()
+    }
+
+    let mut data = (position, false);
+    // SAFETY: check only reads the monitors it's handed and writes to data, which lives until
+    // this returns
+    unsafe {
+        EnumDisplayMonitors(
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            Some(check),
+            &raw mut data as LPARAM,
+        );
+    }
+    data.1
+}
+
+/// Window managers elsewhere keep windows on screen by themselves.
+#[cfg(not(windows))]
+const fn on_a_monitor(_position: WindowPosition) -> bool {
+    true
+}
+
+/// Names the console after the game in builds that have one, so it doesn't read spoofer.exe.
+#[cfg(windows)]
+fn set_console_title(title: &str) {
+    use windows_sys::Win32::System::Console::SetConsoleTitleW;
+    let title: Vec<u16> = title.encode_utf16().chain(std::iter::once(0)).collect();
+    // SAFETY: the title is nul terminated and lives until the call returns
+    if unsafe { SetConsoleTitleW(title.as_ptr()) } == 0 {
+        tracing::debug!("No console to set the title of");
+    }
+}
+
+/// Only windows gives the spoofer a console of its own.
+#[cfg(not(windows))]
+const fn set_console_title(_title: &str) {}
+
+/// Answers `doppelgamer status` with the seconds left on a named pipe of its own, and
+/// `doppelgamer stop` by ending the session, on a thread that lives as long as the process does.
+#[cfg(windows)]
+fn serve_status(seconds_left: &Arc<AtomicU32>) {
+    let seconds_left = Arc::clone(seconds_left);
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                warn!("Failed to start the status pipe: {e}");
+                return;
+            }
+        };
+        if let Err(e) = runtime.block_on(status_server(&seconds_left)) {
+            warn!("Status pipe stopped: {e}");
+        }
+    });
+}
+
+/// The launcher falls back on checking the process list without it.
+#[cfg(not(windows))]
+const fn serve_status(_seconds_left: &Arc<AtomicU32>) {}
+
+#[cfg(windows)]
+async fn status_server(seconds_left: &AtomicU32) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+    let name = doppelgamer::spoofers::status_pipe_name(process::id());
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)?;
+    loop {
+        server.connect().await?;
+        let client = server;
+        // the next instance has to be up before this one is answered, or a status check
+        // arriving in between would find no pipe at all
+        server = ServerOptions::new().create(&name)?;
+        // one stuck client shouldn't keep the rest waiting
+        match tokio::time::timeout(Duration::from_secs(1), answer_status(client, seconds_left))
+            .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::debug!("Failed to answer a status request: {e}"),
+            Err(_) => tracing::debug!("Status request timed out"),
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn answer_status(
+    mut pipe: tokio::net::windows::named_pipe::NamedPipeServer,
+    seconds_left: &AtomicU32,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+    let mut request = String::new();
+    BufReader::new(&mut pipe).read_line(&mut request).await?;
+    match request.trim() {
+        "status" => {
+            let answer = format!("{}\n", seconds_left.load(Ordering::Relaxed));
+            pipe.write_all(answer.as_bytes()).await?;
+        }
+        "stop" => {
+            // a permit is kept if nothing is waiting yet, so this can't be missed
+            STOP.notify_one();
+            pipe.write_all(b"stopping\n").await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+struct Tray {
+    _icon: tray_icon::TrayIcon,
+    show: tray_icon::menu::MenuId,
+    quit: tray_icon::menu::MenuId,
+}
+
+#[cfg(windows)]
+impl Tray {
+    fn new(tooltip: &str) -> anyhow::Result<Self> {
+        let image =
+            image::load_from_memory_with_format(ICON, image::ImageFormat::Ico)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        let icon = tray_icon::Icon::from_rgba(image.into_raw(), width, height)?;
+
+        let show = tray_icon::menu::MenuItem::new("Show", true, None);
+        let quit = tray_icon::menu::MenuItem::new("Quit now", true, None);
+        let menu = tray_icon::menu::Menu::new();
+        menu.append(&show)?;
+        menu.append(&quit)?;
+
+        let icon = tray_icon::TrayIconBuilder::new()
+            .with_icon(icon)
+            .with_tooltip(tooltip)
+            .with_menu(Box::new(menu))
+            .build()?;
+
+        Ok(Self {
+            _icon: icon,
+            show: show.id().clone(),
+            quit: quit.id().clone(),
+        })
+    }
+
+    /// Returns the most recent menu click since the last poll.
+    fn poll(&self) -> Option<TrayAction> {
+        let mut action = None;
+        while let Ok(event) = tray_icon::menu::MenuEvent::receiver().try_recv() {
+            if event.id == self.quit {
+                return Some(TrayAction::Quit);
+            }
+            if event.id == self.show {
+                action = Some(TrayAction::Show);
+            }
+        }
+        action
+    }
+}
+
+/// The tray needs a gtk event loop outside of windows, which iced doesn't run.
+#[cfg(not(windows))]
+struct Tray;
+
+#[cfg(not(windows))]
+impl Tray {
+    fn new(_tooltip: &str) -> anyhow::Result<Self> {
+        anyhow::bail!("tray mode is only supported on windows")
+    }
+
+    #[allow(clippy::unused_self)]
+    const fn poll(&self) -> Option<TrayAction> {
+        None
+    }
+}
+
+/// Where "Stop now" has got to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StopState {
+    Idle,
+    /// Clicked once, and asking to be clicked again.
+    Confirming,
+    /// Ends the session on the next tick as though the time were up.
+    Requested,
+}
 
 struct App {
     game_name: String,
     duration: Span,
+    max_minutes: u32,
+    grace: Span,
+    /// When the session started, moved forward by any time spent paused.
+    start_time: Zoned,
     quit_time: Zoned,
+    time_format: String,
+    refresh_secs: u32,
+    countdown: CountdownStyle,
+    /// Shown under the game unless it's empty.
+    status: Option<String>,
     remaining: String,
+    elapsed: String,
+    progress: f32,
+    paused_at: Option<Zoned>,
+    stop: StopState,
+    /// Whether the time remaining was just copied, which is said next to it until the next tick.
+    copied: bool,
+    loop_session: bool,
+    notify: bool,
+    /// Each alert and whether it has gone off yet this session.
+    alerts: Vec<(Alert, bool)>,
+    flash_secs: u32,
+    /// Whether the taskbar flash has gone off since the time left last dropped below
+    /// `flash_secs`.
+    flashed: bool,
+    /// The quit time last written to the session file, so it's only written again once the quit
+    /// time moves.
+    saved_quit_time: Option<jiff::Timestamp>,
+    /// Where the window was last moved to, saved on the next tick so that dragging it around
+    /// doesn't write the file over and over.
+    unsaved_position: Option<WindowPosition>,
+    tray: Option<Tray>,
+    presence: Option<Presence>,
+    theme: ThemeChoice,
+    skin: SpooferSkin,
+    scale: f32,
+    /// What the status pipe tells the launcher.
+    seconds_left: Arc<AtomicU32>,
+    window_id: Option<iced::window::Id>,
+    /// Set by `--minimized` until the window has opened and been minimized.
+    start_minimized: bool,
     mode: iced::theme::Mode,
 }
 
 #[derive(Copy, Clone, Debug)]
 enum Message {
     Tick,
+    PollTray,
+    Extend,
+    TogglePause,
+    Stop,
+    CopyRemaining,
+    Interrupted,
+    StopRequested,
+    CloseRequested(iced::window::Id),
+    Resized(iced::window::Id, iced::Size),
+    Moved(iced::Point),
+    Opened(iced::window::Id),
     SystemThemeChanged(iced::theme::Mode),
 }
 
 impl App {
-    fn boot() -> (Self, iced::Task<Message>) {
-        let args: Vec<String> = std::env::args().collect();
-        if args.len() < 3 {
-            eprintln!("Usage: spoofer <game_name> <duration_minutes>");
-            process::exit(1);
-        }
-        let game_name = args[1].clone();
-        let minutes: i64 = args[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid duration: {}", args[2]);
-            process::exit(1);
-        });
-        let duration = Span::new().minutes(minutes);
-        let quit_time = Zoned::now()
-            .round(Unit::Second)
-            .expect("Rounding failed")
-            .saturating_add(duration)
-            .saturating_add(Span::new().seconds(15));
-
-        let remaining = format_remaining(&quit_time);
-
-        (
-            Self {
-                game_name,
-                duration,
-                quit_time,
-                remaining,
-                mode: iced::theme::Mode::None,
-            },
-            iced::system::theme().map(Message::SystemThemeChanged),
-        )
+    fn boot(args: &Args, seconds_left: Arc<AtomicU32>) -> (Self, iced::Task<Message>) {
+        let grace = grace(args);
+        let (duration, start_time, quit_time) = resume_or_schedule(args, grace);
+
+        let tray = if args.tray {
+            Tray::new(&args.game_name)
+                .inspect_err(|e| warn!("Failed to create tray icon: {e:#}"))
+                .ok()
+        } else {
+            None
+        };
+
+        info!(game = %args.game_name, %quit_time, "Spoofing");
+        let mut presence = Presence::connect(args);
+        if let Some(presence) = &mut presence {
+            presence.show(&start_time);
+        }
+
+        let mut app = Self {
+            game_name: args.game_name.clone(),
+            duration,
+            max_minutes: args.max_minutes,
+            grace,
+            start_time,
+            quit_time,
+            time_format: args.time_format.clone(),
+            refresh_secs: args.refresh_secs,
+            countdown: args.countdown,
+            status: args
+                .status
+                .clone()
+                .filter(|status| !status.trim().is_empty()),
+            remaining: String::new(),
+            elapsed: String::new(),
+            progress: 0.0,
+            paused_at: None,
+            stop: StopState::Idle,
+            copied: false,
+            loop_session: args.loop_session,
+            notify: args.notify,
+            alerts: args.alerts.0.iter().map(|&alert| (alert, false)).collect(),
+            flash_secs: args.flash_secs,
+            flashed: false,
+            saved_quit_time: None,
+            unsaved_position: None,
+            tray,
+            presence,
+            theme: args.theme,
+            skin: args.skin,
+            scale: args.scale,
+            seconds_left,
+            window_id: None,
+            start_minimized: args.minimized,
+            mode: iced::theme::Mode::None,
+        };
+        app.refresh_remaining();
+        app.remember_session();
+
+        (app, iced::system::theme().map(Message::SystemThemeChanged))
     }
 
+    /// Keeps the real game name up front and the countdown visible on the taskbar, since iced
+    /// asks for the title again after every tick.
     fn title(&self) -> String {
-        format!("Doppelgamer - {} ({})", self.game_name, self.remaining)
+        if self.paused_at.is_some() {
+            format!("{} — paused, {} left", self.game_name, self.remaining)
+        } else {
+            format!("{} — {} left", self.game_name, self.remaining)
+        }
     }
 
-    fn update(&mut self, msg: Message) {
+    fn update(&mut self, msg: Message) -> iced::Task<Message> {
         match msg {
             Message::Tick => {
-                if Zoned::now() > self.quit_time {
-                    process::exit(0);
+                self.copied = false;
+                self.save_position();
+                let stop_requested = self.stop == StopState::Requested;
+                if self.paused_at.is_some() && !stop_requested {
+                    return iced::Task::none();
+                }
+                if stop_requested || remaining(&Zoned::now(), &self.quit_time).is_none() {
+                    if self.notify {
+                        notify(&format!("{} session finished", self.game_name));
+                    }
+                    if stop_requested {
+                        info!("Stopped by hand, exiting");
+                        return iced::exit();
+                    }
+                    if !self.loop_session {
+                        info!("Time's up, exiting");
+                        return iced::exit();
+                    }
+                    (self.start_time, self.quit_time) = schedule(self.duration, self.grace);
+                    info!(quit_time = %self.quit_time, "Time's up, starting over");
+                    self.show_presence();
+                    for (_, fired) in &mut self.alerts {
+                        *fired = false;
+                    }
+                }
+                self.remember_session();
+                self.refresh_remaining();
+                return iced::Task::batch([self.check_alerts(), self.check_flash()]);
+            }
+            Message::PollTray => return self.poll_tray(),
+            Message::Extend => {
+                self.cancel_stop();
+                let extension = Span::new().minutes(EXTEND_MINUTES);
+                // stick to the same cap as the command line
+                if let Ok(duration) = self.duration.checked_add(extension)
+                    && doppelgamer::duration_minutes(duration)
+                        .is_ok_and(|minutes| minutes <= f64::from(self.max_minutes))
+                {
+                    self.duration = duration;
+                    self.quit_time = self.quit_time.saturating_add(extension);
+                    self.refresh_remaining();
+                }
+            }
+            Message::TogglePause => {
+                self.cancel_stop();
+                if let Some(paused_at) = self.paused_at.take() {
+                    // push the deadline back by however long we were paused for
+                    if let Ok(paused_for) = Zoned::now().since(&paused_at) {
+                        self.start_time = self.start_time.saturating_add(paused_for);
+                        self.quit_time = self.quit_time.saturating_add(paused_for);
+                        self.show_presence();
+                    }
+                    self.refresh_remaining();
+                } else {
+                    self.paused_at = Some(Zoned::now());
+                }
+            }
+            Message::Stop => {
+                if self.stop == StopState::Idle {
+                    self.stop = StopState::Confirming;
+                    return iced::Task::none();
+                }
+                // go out the same way as running out of time, rather than straight away
+                self.stop = StopState::Requested;
+                return iced::Task::done(Message::Tick);
+            }
+            Message::CopyRemaining => {
+                self.copied = true;
+                return iced::clipboard::write(self.remaining.clone());
+            }
+            Message::Interrupted => {
+                // leaving through iced lets main record the end of the session
+                info!("Interrupted, exiting");
+                self.save_position();
+                return iced::exit();
+            }
+            Message::StopRequested => {
+                info!("Asked to stop through the status pipe");
+                self.stop = StopState::Requested;
+                return iced::Task::done(Message::Tick);
+            }
+            Message::CloseRequested(id) => {
+                self.save_position();
+                if self.tray.is_none() {
+                    info!("Window closed, exiting");
+                    return iced::exit();
+                }
+                return self.hide(id);
+            }
+            Message::Resized(id, size) => {
+                // windows reports minimizing as a resize down to nothing
+                if size.width == 0.0 && size.height == 0.0 {
+                    return self.hide(id);
+                }
+            }
+            Message::Moved(point) => {
+                let position = WindowPosition {
+                    x: point.x,
+                    y: point.y,
+                };
+                if on_a_monitor(position) {
+                    self.unsaved_position = Some(position);
+                }
+            }
+            Message::Opened(id) => {
+                if self.start_minimized {
+                    self.start_minimized = false;
+                    // with --tray this goes on to hide in the tray, like any other minimize
+                    return iced::window::minimize(id, true);
                 }
-                self.remaining = format_remaining(&self.quit_time);
             }
             Message::SystemThemeChanged(mode) => {
                 self.mode = mode;
             }
         }
+        iced::Task::none()
+    }
+
+    fn show_presence(&mut self) {
+        if let Some(presence) = &mut self.presence {
+            presence.show(&self.start_time);
+        }
+    }
+
+    /// Writes out the last place the window was moved to. This happens every tick rather than on
+    /// every move, so it's also done on the way out: stopping and running out of time already
+    /// go through a tick, but closing the window and being interrupted don't.
+    fn save_position(&mut self) {
+        if let Some(position) = self.unsaved_position.take()
+            && let Err(e) = save_window_position(position)
+        {
+            warn!("Failed to remember the window position: {e:#}");
+        }
+    }
+
+    /// Clicking anything else takes back a first click on "Stop now".
+    fn cancel_stop(&mut self) {
+        if self.stop == StopState::Confirming {
+            self.stop = StopState::Idle;
+        }
+    }
+
+    fn hide(&mut self, id: iced::window::Id) -> iced::Task<Message> {
+        if self.tray.is_none() {
+            return iced::window::close(id);
+        }
+        self.window_id = Some(id);
+        iced::window::set_mode(id, iced::window::Mode::Hidden)
+    }
+
+    fn seconds_left(&self) -> f64 {
+        remaining(&Zoned::now(), &self.quit_time)
+            .and_then(|left| {
+                left.total((Unit::Second, SpanRelativeTo::days_are_24_hours()))
+                    .ok()
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// Sets off any alerts whose point in the session has been reached, each only once, and
+    /// asks for attention on the taskbar if there were any.
+    fn check_alerts(&mut self) -> iced::Task<Message> {
+        let left = self.seconds_left();
+        let mut any = false;
+        for (alert, fired) in &mut self.alerts {
+            let due = match alert {
+                Alert::Elapsed(percent) => self.progress * 100.0 >= f32::from(*percent),
+                Alert::Remaining(span) => span
+                    .total((Unit::Second, SpanRelativeTo::days_are_24_hours()))
+                    .is_ok_and(|span| left <= span),
+            };
+            if due && !*fired {
+                *fired = true;
+                any = true;
+                info!(%alert, "Alert");
+                notify(&format!("{}: {} left", self.game_name, self.remaining));
+            }
+        }
+        if any {
+            request_attention()
+        } else {
+            iced::Task::none()
+        }
+    }
+
+    /// Flashes the taskbar entry when the time left drops below `flash_secs`, once per
+    /// crossing, so extending the session past it and running low again flashes again.
+    fn check_flash(&mut self) -> iced::Task<Message> {
+        if self.flash_secs == 0 {
+            return iced::Task::none();
+        }
+        if self.seconds_left() > f64::from(self.flash_secs) {
+            self.flashed = false;
+            return iced::Task::none();
+        }
+        if self.flashed {
+            return iced::Task::none();
+        }
+        self.flashed = true;
+        info!("Nearly time, flashing the taskbar");
+        request_attention()
+    }
+
+    /// Handles any tray menu clicks since the last poll, returning a task to bring the window
+    /// back if "Show" was picked.
+    fn poll_tray(&self) -> iced::Task<Message> {
+        match self.tray.as_ref().and_then(Tray::poll) {
+            Some(TrayAction::Quit) => {
+                info!("Quit from the tray, exiting");
+                iced::exit()
+            }
+            Some(TrayAction::Show) => self.window_id.map_or_else(iced::Task::none, |id| {
+                iced::window::set_mode(id, iced::window::Mode::Windowed)
+                    .chain(iced::window::minimize(id, false))
+                    .chain(iced::window::gain_focus(id))
+            }),
+            None => iced::Task::none(),
+        }
+    }
+
+    /// Checked every tick, but only written when extending, pausing or starting over has moved
+    /// the quit time. Failing to write is only logged, since it only matters after a crash.
+    fn remember_session(&mut self) {
+        let quit_time = self.quit_time.timestamp();
+        if self.saved_quit_time == Some(quit_time) {
+            return;
+        }
+        self.saved_quit_time = Some(quit_time);
+        let session = SavedSession {
+            game: self.game_name.clone(),
+            duration: self.duration,
+            start_time: self.start_time.timestamp(),
+            quit_time,
+        };
+        if let Err(e) = save_session(&session) {
+            warn!("Failed to save the session: {e:#}");
+        }
+    }
+
+    /// Remaining and elapsed time stay frozen at the moment of pausing until the session
+    /// resumes.
+    fn refresh_remaining(&mut self) {
+        let now = self.paused_at.clone().unwrap_or_else(Zoned::now);
+        self.remaining = format_remaining(&now, &self.quit_time, self.refresh_secs, self.countdown);
+        self.elapsed = format_elapsed(&self.start_time, &now, self.refresh_secs, self.countdown);
+        self.progress = progress(&now, &self.quit_time, self.duration, self.grace);
+        self.seconds_left
+            .store(whole_seconds_left(&now, &self.quit_time), Ordering::Relaxed);
+    }
+
+    /// The quit time in the chosen format. The format was checked when parsing the arguments,
+    /// but formatting can still fail on unusual times, and [`Zoned::strftime`] would panic.
+    fn exit_time(&self) -> String {
+        jiff::fmt::strtime::format(&self.time_format, &self.quit_time).unwrap_or_else(|e| {
+            warn!("Failed to format exit time: {e}");
+            self.quit_time
+                .strftime(doppelgamer::DEFAULT_TIME_FORMAT)
+                .to_string()
+        })
+    }
+
+    const fn scale_factor(&self) -> f32 {
+        self.scale
     }
 
     const fn theme(&self) -> iced::Theme {
-        match self.mode {
-            iced::theme::Mode::Dark => iced::Theme::CatppuccinMocha,
-            iced::theme::Mode::Light | iced::theme::Mode::None => iced::Theme::CatppuccinLatte,
+        let dark = match self.theme {
+            ThemeChoice::System => matches!(self.mode, iced::theme::Mode::Dark),
+            ThemeChoice::Light => false,
+            ThemeChoice::Dark => true,
+        };
+        if dark {
+            iced::Theme::CatppuccinMocha
+        } else {
+            iced::Theme::CatppuccinLatte
         }
     }
 
     fn view(&self) -> iced::Element<'_, Message> {
+        match self.skin {
+            SpooferSkin::Classic => self.classic_view(),
+            SpooferSkin::Minimal => self.minimal_view(),
+            SpooferSkin::Loading => self.loading_view(),
+        }
+    }
+
+    fn classic_view(&self) -> iced::Element<'_, Message> {
+        let mut playing = iced::widget::column![iced::widget::text(format!(
+            "Playing {} for {:?}",
+            self.game_name, self.duration
+        ))]
+        .spacing(5);
+        if let Some(status) = &self.status {
+            playing = playing.push(iced::widget::text(status).size(14));
+        }
+
         iced::widget::column![
             iced::widget::text("Doppelgamer").size(24),
-            iced::widget::text(format!(
-                "Playing {} for {:?}",
-                self.game_name, self.duration
-            )),
-            iced::widget::text(format!(
-                "Will exit at: {:#}",
-                self.quit_time.strftime("%H:%M:%S")
-            )),
-            iced::widget::text(format!("Time remaining: {}", self.remaining)),
+            playing,
+            iced::widget::text(if self.grace.is_zero() {
+                format!("Will exit at: {}", self.exit_time())
+            } else {
+                format!(
+                    "Will exit at: {} (includes {:#} grace)",
+                    self.exit_time(),
+                    self.grace
+                )
+            }),
+            self.remaining_text(format!("Time remaining: {}", self.remaining), 16.0),
+            iced::widget::text(format!("Elapsed: {}", self.elapsed)),
+            iced::widget::progress_bar(0.0..=1.0, self.progress),
+            self.buttons(),
         ]
         .spacing(5)
         .padding(10)
         .into()
     }
 
-    #[allow(clippy::unused_self)]
+    /// Just the game, the time left and the buttons.
+    fn minimal_view(&self) -> iced::Element<'_, Message> {
+        iced::widget::column![
+            iced::widget::text(&self.game_name).size(18),
+            self.remaining_text(format!("{} left", self.remaining), 32.0),
+            self.buttons(),
+        ]
+        .spacing(5)
+        .padding(10)
+        .into()
+    }
+
+    /// Dressed up as the game's own loading screen, with the session as the progress bar.
+    fn loading_view(&self) -> iced::Element<'_, Message> {
+        let mut loading = iced::widget::column![
+            iced::widget::text(format!("Loading {}...", self.game_name)).size(24),
+            iced::widget::progress_bar(0.0..=1.0, self.progress),
+            self.remaining_text(format!("About {} remaining", self.remaining), 14.0),
+        ]
+        .spacing(5)
+        .align_x(iced::Center);
+        if let Some(status) = &self.status {
+            loading = loading.push(iced::widget::text(status).size(14));
+        }
+        iced::widget::column![loading, iced::widget::space::vertical(), self.buttons()]
+            .spacing(5)
+            .padding(10)
+            .align_x(iced::Center)
+            .into()
+    }
+
+    /// Extending, pausing and stopping work the same whichever skin is showing.
+    fn buttons(&self) -> iced::widget::Row<'_, Message> {
+        iced::widget::row![
+            iced::widget::button(iced::widget::text(format!("+{EXTEND_MINUTES} min")))
+                .style(iced::widget::button::secondary)
+                .on_press(Message::Extend),
+            iced::widget::button(if self.paused_at.is_some() {
+                "Resume"
+            } else {
+                "Pause"
+            })
+            .style(iced::widget::button::secondary)
+            .on_press(Message::TogglePause),
+            iced::widget::button(if self.stop == StopState::Confirming {
+                "Click again to stop"
+            } else {
+                "Stop now"
+            })
+            .style(iced::widget::button::danger)
+            .on_press(Message::Stop),
+        ]
+        .spacing(5)
+    }
+
+    /// The time remaining in every skin can be clicked to copy it.
+    fn remaining_text(&self, label: String, size: f32) -> iced::Element<'_, Message> {
+        let label = if self.copied {
+            format!("{label} (copied)")
+        } else {
+            label
+        };
+        iced::widget::mouse_area(iced::widget::text(label).size(size))
+            .on_press(Message::CopyRemaining)
+            .interaction(iced::mouse::Interaction::Pointer)
+            .into()
+    }
+
     fn subscription(&self) -> iced::Subscription<Message> {
-        iced::Subscription::batch([
-            iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick),
+        let mut subscriptions = vec![
+            iced::time::every(tick_interval(
+                self.refresh_secs,
+                self.seconds_left.load(Ordering::Relaxed),
+            ))
+            .map(|_| Message::Tick),
             iced::system::theme_changes().map(Message::SystemThemeChanged),
-        ])
+            iced::Subscription::run(interrupts),
+            iced::Subscription::run(stop_requests),
+            // handled here rather than by iced, so the window position is saved on the way out
+            iced::window::close_requests().map(Message::CloseRequested),
+            iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Window(iced::window::Event::Moved(point)) => {
+                    Some(Message::Moved(point))
+                }
+                _ => None,
+            }),
+        ];
+        if self.start_minimized {
+            subscriptions.push(iced::window::open_events().map(Message::Opened));
+        }
+        if self.tray.is_some() {
+            subscriptions.push(iced::time::every(TRAY_POLL_INTERVAL).map(|_| Message::PollTray));
+            subscriptions
+                .push(iced::window::resize_events().map(|(id, size)| Message::Resized(id, size)));
+        }
+        iced::Subscription::batch(subscriptions)
+    }
+}
+
+/// Cuts a session longer than `max_minutes` down to it, since a duration that long is far more
+/// likely to be a typo than a real request. Nothing stops the spoofer being run by hand, so the
+/// launcher's own limit can't be relied on.
+fn cap_duration(duration: Span, max_minutes: u32) -> Span {
+    match doppelgamer::duration_minutes(duration) {
+        Ok(minutes) if minutes <= f64::from(max_minutes) => duration,
+        _ => {
+            warn!(%duration, max_minutes, "Session is longer than allowed, cutting it short");
+            Span::new().minutes(max_minutes)
+        }
     }
 }
 
-fn format_remaining(quit_time: &Zoned) -> String {
-    let remaining = Zoned::now()
+fn grace(args: &Args) -> Span {
+    Span::new().seconds(
+        args.grace_secs
+            .or_else(doppelgamer::grace_secs_from_env)
+            .unwrap_or(doppelgamer::DEFAULT_GRACE_SECS),
+    )
+}
+
+/// When a session starting now begins and ends, to the second. Shared by the window and
+/// headless mode so that both quit at the same moment.
+fn schedule(duration: Span, grace: Span) -> (Zoned, Zoned) {
+    let now = Zoned::now();
+    let start_time = now.round(Unit::Second).unwrap_or(now);
+    let mut quit_time = start_time.saturating_add(duration).saturating_add(grace);
+    // only a clock so far off that adding to it runs out of range gets here, and exiting on the
+    // first tick with no word why would be baffling
+    if quit_time <= start_time {
+        warn!(
+            %start_time,
+            "The session would be over before it began, check the system clock. Running for a \
+             minute instead"
+        );
+        quit_time = start_time.saturating_add(Span::new().minutes(1));
+    }
+    (start_time, quit_time)
+}
+
+/// The session to pick up again with `--resume`, or else a new one. Only a saved session for the
+/// same game that hasn't run out yet is picked up.
+fn resume_or_schedule(args: &Args, grace: Span) -> (Span, Zoned, Zoned) {
+    if args.resume {
+        match load_session() {
+            Some(saved)
+                if saved.game == args.game_name && saved.quit_time > jiff::Timestamp::now() =>
+            {
+                info!(quit_time = %saved.quit_time, "Resuming an unfinished session");
+                let tz = jiff::tz::TimeZone::system();
+                return (
+                    saved.duration,
+                    saved.start_time.to_zoned(tz.clone()),
+                    saved.quit_time.to_zoned(tz),
+                );
+            }
+            Some(_) => info!("The saved session is over or for another game, starting a new one"),
+            None => info!("No unfinished session to resume, starting a new one"),
+        }
+    }
+    let (start_time, quit_time) = schedule(args.duration, grace);
+    (args.duration, start_time, quit_time)
+}
+
+/// Sends [`Message::Interrupted`] on Ctrl+C in the console, which would otherwise end the
+/// process on the spot.
+fn interrupts() -> impl iced::futures::Stream<Item = Message> {
+    use iced::futures::SinkExt as _;
+    iced::stream::channel(1, async |mut output| match tokio::signal::ctrl_c().await {
+        Ok(()) => {
+            let _ = output.send(Message::Interrupted).await;
+        }
+        Err(e) => warn!("Failed to listen for Ctrl+C: {e}"),
+    })
+}
+
+/// Sends [`Message::StopRequested`] each time the status pipe is asked to stop.
+fn stop_requests() -> impl iced::futures::Stream<Item = Message> {
+    use iced::futures::SinkExt as _;
+    iced::stream::channel(1, async |mut output| {
+        loop {
+            STOP.notified().await;
+            if output.send(Message::StopRequested).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Waits out the session without a window, checking the clock every second like the window
+/// does, until the time is up, Ctrl+C is pressed or the status pipe is asked to stop.
+fn run_headless(args: &Args, seconds_left: &AtomicU32) -> anyhow::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let grace = grace(args);
+    runtime.block_on(async {
+        // kept across waits so a Ctrl+C between two of them isn't missed
+        let ctrl_c = std::pin::pin!(async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                warn!("Failed to listen for Ctrl+C: {e}");
+                std::future::pending::<()>().await;
+            }
+            "Interrupted"
+        });
+        let stop = std::pin::pin!(async {
+            STOP.notified().await;
+            "Asked to stop through the status pipe"
+        });
+        let mut ended_early = iced::futures::future::select(ctrl_c, stop);
+        let (mut duration, mut start_time, mut quit_time) = resume_or_schedule(args, grace);
+        let mut presence = Presence::connect(args);
+        loop {
+            info!(game = %args.game_name, %quit_time, "Spoofing without a window");
+            if let Some(presence) = &mut presence {
+                presence.show(&start_time);
+            }
+            let session = SavedSession {
+                game: args.game_name.clone(),
+                duration,
+                start_time: start_time.timestamp(),
+                quit_time: quit_time.timestamp(),
+            };
+            if let Err(e) = save_session(&session) {
+                warn!("Failed to save the session: {e:#}");
+            }
+            while remaining(&Zoned::now(), &quit_time).is_some() {
+                let left = whole_seconds_left(&Zoned::now(), &quit_time);
+                seconds_left.store(left, Ordering::Relaxed);
+                if let Ok(ended) =
+                    tokio::time::timeout(tick_interval(args.refresh_secs, left), &mut ended_early)
+                        .await
+                {
+                    let (iced::futures::future::Either::Left((reason, _))
+                    | iced::futures::future::Either::Right((reason, _))) = ended;
+                    info!("{reason}, exiting");
+                    return Ok(());
+                }
+            }
+            if args.notify {
+                notify(&format!("{} session finished", args.game_name));
+            }
+            if !args.loop_session {
+                info!("Time's up, exiting");
+                return Ok(());
+            }
+            info!("Time's up, starting over");
+            duration = args.duration;
+            (start_time, quit_time) = schedule(duration, grace);
+        }
+    })
+}
+
+/// Time left until `quit_time`, to the second. `None` once it has passed, or if the clock has
+/// jumped somewhere it can't be worked out from, which is treated the same way.
+fn remaining(now: &Zoned, quit_time: &Zoned) -> Option<Span> {
+    now.until(quit_time)
+        .and_then(|remaining| remaining.round(Unit::Second))
+        .ok()
+        .filter(|remaining| !remaining.is_negative())
+}
+
+/// Time left as whole seconds, for the status pipe.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn whole_seconds_left(now: &Zoned, quit_time: &Zoned) -> u32 {
+    // float to int casts saturate, so a time already up comes out as 0
+    now.until(quit_time)
+        .and_then(|left| left.total(Unit::Second))
+        .map_or(0, |left| left as u32)
+}
+
+/// Waits `refresh_secs` between ticks, but no longer than the time left, so the session still
+/// ends on the second however coarse the refresh is.
+fn tick_interval(refresh_secs: u32, seconds_left: u32) -> Duration {
+    Duration::from_secs(u64::from(refresh_secs.min(seconds_left).max(1)))
+}
+
+/// Rounded up to a step of `refresh_secs`, so the countdown never shows less time than is
+/// really left.
+fn format_remaining(
+    now: &Zoned,
+    quit_time: &Zoned,
+    refresh_secs: u32,
+    style: CountdownStyle,
+) -> String {
+    let round = SpanRound::new()
+        .smallest(Unit::Second)
+        .increment(i64::from(refresh_secs))
+        .mode(RoundMode::Ceil);
+    let remaining = remaining(now, quit_time)
+        .and_then(|remaining| remaining.round(round).ok())
+        .unwrap_or_default();
+    format_span(remaining, style)
+}
+
+/// Expects a span rounded to seconds and balanced up to hours at most, as [`Zoned::until`]
+/// gives, so the clock only has to add up hours, minutes and seconds.
+fn format_span(span: Span, style: CountdownStyle) -> String {
+    match style {
+        CountdownStyle::Words => format!("{span:#}"),
+        CountdownStyle::Clock => {
+            let secs =
+                (i64::from(span.get_hours()) * 60 + span.get_minutes()) * 60 + span.get_seconds();
+            format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        }
+    }
+}
+
+/// Flashes the window's taskbar entry until it is focused. On windows winit does this with
+/// `FlashWindowEx`.
+fn request_attention() -> iced::Task<Message> {
+    iced::window::latest().and_then(|id| {
+        iced::window::request_user_attention(id, Some(iced::window::UserAttention::Informational))
+    })
+}
+
+/// Time since the session started, rounded down to a step of `refresh_secs` and formatted like
+/// the time remaining.
+fn format_elapsed(
+    start_time: &Zoned,
+    now: &Zoned,
+    refresh_secs: u32,
+    style: CountdownStyle,
+) -> String {
+    let round = SpanRound::new()
+        .smallest(Unit::Second)
+        .increment(i64::from(refresh_secs))
+        .mode(RoundMode::Floor);
+    let elapsed = start_time
+        .until(now)
+        .and_then(|elapsed| elapsed.round(round))
+        .ok()
+        .filter(|elapsed| !elapsed.is_negative())
+        .unwrap_or_default();
+    format_span(elapsed, style)
+}
+
+/// Posts a desktop notification. Failing to is only logged, since nothing depends on it.
+fn notify(body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .summary("Doppelgamer")
+        .body(body)
+        .show()
+    {
+        warn!("Failed to show a notification: {e}");
+    }
+}
+
+/// Fraction of the session that has elapsed, grace period included, so the bar fills right as
+/// the spoofer exits.
+#[allow(clippy::cast_possible_truncation)]
+fn progress(now: &Zoned, quit_time: &Zoned, duration: Span, grace: Span) -> f32 {
+    let Ok(total) = duration
+        .checked_add(grace)
+        .and_then(|total| total.total(Unit::Second))
+    else {
+        return 0.0;
+    };
+    let Ok(remaining) = now
         .until(quit_time)
-        .expect("Time went backwards")
-        .round(Unit::Second)
-        .expect("Rounding failed");
-    format!("{remaining:#}")
+        .and_then(|remaining| remaining.total(Unit::Second))
+    else {
+        return 0.0;
+    };
+    if total <= 0.0 {
+        return 1.0;
+    }
+    (1.0 - remaining / total).clamp(0.0, 1.0) as f32
 }
 
 fn main() -> anyhow::Result<()> {
-    iced::application(App::boot, App::update, App::view)
+    let mut args = Args::parse();
+    doppelgamer::set_dirs(args.config_dir.clone(), args.data_dir.clone())?;
+    doppelgamer::init_logging("spoofer.log", false, false)?;
+    args.duration = cap_duration(args.duration, args.max_minutes);
+    set_console_title(&args.game_name);
+    let icon = args
+        .icon
+        .as_deref()
+        .and_then(|path| {
+            iced::window::icon::from_file(path)
+                .inspect_err(|e| warn!("Failed to load icon {}: {e}", path.display()))
+                .ok()
+        })
+        .unwrap_or_else(|| {
+            iced::window::icon::from_file_data(ICON, None).expect("embedded icon should be valid")
+        });
+    let level = if args.always_on_top {
+        iced::window::Level::AlwaysOnTop
+    } else {
+        iced::window::Level::Normal
+    };
+    let resizable = args.resizable;
+
+    // held until run() returns, which it does on every way out now that nothing calls
+    // process::exit, and it has to stay on this thread since the flags are per thread
+    #[cfg(windows)]
+    let _keep_awake = if args.keep_awake {
+        match KeepAwake::new() {
+            Ok(keep_awake) => {
+                info!("Keeping the system awake");
+                Some(keep_awake)
+            }
+            Err(e) => {
+                warn!("Failed to keep the system awake: {e:#}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    #[cfg(not(windows))]
+    if args.keep_awake {
+        warn!("--keep-awake is only supported on windows");
+    }
+    let (width, height) = args.window_size.unwrap_or(doppelgamer::DEFAULT_WINDOW_SIZE);
+    // the size is in the same units as everything drawn in it, so it grows along with them
+    let size = iced::Size::new(f32::from(width), f32::from(height)) * args.scale;
+
+    let seconds_left = Arc::new(AtomicU32::new(0));
+    serve_status(&seconds_left);
+    if args.headless {
+        run_headless(&args, &seconds_left)?;
+    } else {
+        iced::application(
+            move || App::boot(&args, Arc::clone(&seconds_left)),
+            App::update,
+            App::view,
+        )
         .title(App::title)
         .theme(App::theme)
+        .scale_factor(App::scale_factor)
         .window(iced::window::Settings {
-            size: iced::Size::new(320.0, 130.0),
-            resizable: false,
-            icon: Some(
-                iced::window::icon::from_file_data(ICON, None)
-                    .expect("embedded icon should be valid"),
+            size,
+            position: load_window_position().map_or_else(
+                iced::window::Position::default,
+                |WindowPosition { x, y }| iced::window::Position::Specific(iced::Point::new(x, y)),
             ),
+            resizable,
+            level,
+            // closing only hides the window in tray mode, and exits after saving its position
+            // otherwise
+            exit_on_close_request: false,
+            icon: Some(icon),
             ..Default::default()
         })
         .subscription(App::subscription)
         .run()?;
+    }
+
+    info!("Exited");
+    clear_session();
+    let ended = doppelgamer::HistoryEvent::Ended {
+        pid: process::id(),
+        at: jiff::Timestamp::now(),
+    };
+    if let Err(e) = doppelgamer::append_history(&ended) {
+        warn!("Failed to record the end of this session: {e:#}");
+    }
 
     Ok(())
 }
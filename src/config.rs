@@ -0,0 +1,93 @@
+//! Defaults read from `config.toml`, which command line flags and environment variables take
+//! precedence over.
+use std::fs;
+use std::path::PathBuf;
+
+use tracing::{info, warn};
+
+use crate::config_dir;
+
+/// Written on first run so there is something to edit. Everything is commented out, leaving
+/// the built in defaults in charge until the user says otherwise.
+const DEFAULT_CONFIG: &str = r#"# doppelgamer settings. command line flags win over anything set here.
+
+# minutes the launcher suggests for a new session.
+# default_minutes = 15
+
+# seconds the spoofer waits after the session is up before exiting.
+# grace_secs = 15
+
+# where to download the games list from.
+# games_url = "https://raw.githubusercontent.com/backwardspy/doppelgamer/refs/heads/main/games.v2.json"
+
+# more places to try, in order, if games_url can't be reached.
+# mirrors = ["https://example.com/games.v2.json"]
+
+# hide spoofers in the system tray when closed or minimized. windows only.
+# tray = false
+
+# keep the spoofer window above other windows.
+# always_on_top = false
+
+# show a desktop notification when a session is over.
+# notify = false
+
+# strftime format for the spoofer's exit time, such as "%I:%M %p" for a 12-hour clock.
+# time_format = "%H:%M:%S"
+
+# keep the computer from going to sleep while a spoofer runs. windows only.
+# keep_awake = false
+"#;
+
+/// Every key is optional. Missing keys, like a missing or unreadable file, leave the built in
+/// defaults alone.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub default_minutes: Option<u32>,
+    pub grace_secs: Option<u32>,
+    pub games_url: Option<String>,
+    pub mirrors: Option<Vec<String>>,
+    pub tray: Option<bool>,
+    pub always_on_top: Option<bool>,
+    pub keep_awake: Option<bool>,
+    pub notify: Option<bool>,
+    pub time_format: Option<String>,
+}
+
+/// # Errors
+///
+/// Fails if the config directory can't be found or created.
+pub fn config_path() -> anyhow::Result<PathBuf> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push("config.toml");
+    Ok(path)
+}
+
+/// Reads `config.toml`, writing the commented default file first if there isn't one yet.
+#[must_use]
+pub fn load_config() -> Config {
+    let Ok(path) = config_path() else {
+        warn!("Failed to determine config path, using defaults");
+        return Config::default();
+    };
+    if !path.exists() {
+        match fs::write(&path, DEFAULT_CONFIG) {
+            Ok(()) => info!("Wrote default config to {}", path.display()),
+            Err(e) => warn!("Failed to write default config: {e}"),
+        }
+        return Config::default();
+    }
+    match fs::read_to_string(&path).map(|data| toml::from_str(&data)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(e)) => {
+            warn!("Ignoring invalid {}: {e}", path.display());
+            Config::default()
+        }
+        Err(e) => {
+            warn!("Failed to read {}: {e}", path.display());
+            Config::default()
+        }
+    }
+}
@@ -0,0 +1,1494 @@
+//! The games list: loading it from the local cache, keeping it up to date from the remote copy,
+//! and checking it for entries that can't be spoofed.
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{IsTerminal as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tracing::{debug, error, info, warn};
+
+use crate::{HistoryEvent, config_dir, data_dir, write_atomically};
+
+const BUNDLED_GAMES: &str = include_str!("../games.v2.json");
+/// Where the latest games list is fetched from by default.
+pub const GAMES_JSON_URL: &str =
+    "https://raw.githubusercontent.com/backwardspy/doppelgamer/refs/heads/main/games.v2.json";
+/// Overrides [`GAMES_JSON_URL`].
+pub const GAMES_URL_ENV: &str = "DOPPELGAMER_GAMES_URL";
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+/// How many times a failed connection is retried unless asked otherwise.
+pub const DEFAULT_FETCH_RETRIES: u32 = 3;
+/// Doubled after every failed attempt.
+const RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+const MAX_SUGGESTIONS: usize = 10;
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Layout version written by [`save_games`]. Bump it, and teach [`parse_games`] to bring the
+/// previous layout up to date, whenever [`Game`] changes in a way serde defaults can't paper
+/// over.
+pub const GAMES_SCHEMA_VERSION: u32 = 1;
+
+/// A games list in any of the layouts it has had. Version 0 is the bare array used before the
+/// layout had a version, which the bundled and remote lists still are.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum GamesFile {
+    Versioned { version: u32, games: Vec<Game> },
+    Bare(Vec<Game>),
+}
+
+/// What [`save_games`] writes.
+#[derive(serde::Serialize)]
+struct VersionedGames<'a> {
+    version: u32,
+    games: &'a [Game],
+}
+
+/// Reads a games list in any known layout, returning the games and the version it was in so
+/// that an older file can be written back in the current layout.
+///
+/// # Errors
+///
+/// Fails if the data isn't a games list, or comes from a newer version of doppelgamer.
+pub fn parse_games(data: &[u8]) -> anyhow::Result<(Vec<Game>, u32)> {
+    let (games, version) = match serde_json::from_slice(data)? {
+        GamesFile::Versioned { version, games } => (games, version),
+        GamesFile::Bare(games) => (games, 0),
+    };
+    anyhow::ensure!(
+        version <= GAMES_SCHEMA_VERSION,
+        "The games list is version {version}, but only up to {GAMES_SCHEMA_VERSION} is \
+         understood, so it needs a newer doppelgamer"
+    );
+    // going from 0 to 1 only put the list inside an object, which the match above has undone
+    Ok((games, version))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Game {
+    pub name: String,
+    /// Where the spoofer copy goes, relative to the data directory.
+    pub exe: PathBuf,
+    /// Categories like `fps` for filtering the list. Older lists don't have any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Other names the game goes by, like `CS2`, which find it just as well as its name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// An image to give the spoofer window instead of the snake. Relative paths are looked up
+    /// in the config directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<PathBuf>,
+    /// What Discord shows for the game with `--rpc`: an asset key of the Discord application or
+    /// an image url.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_image: Option<String>,
+    /// Added with `add-game` or `scan` rather than coming from a downloaded list, so downloads
+    /// keep it instead of going by what they have.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+}
+
+impl Game {
+    /// The game's name followed by its aliases.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Checks for a tag, ignoring case.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    /// Where the game's custom icon is, if it has one.
+    #[must_use]
+    pub fn icon_path(&self) -> Option<PathBuf> {
+        let icon = self.icon.as_ref()?;
+        if icon.is_absolute() {
+            return Some(icon.clone());
+        }
+        Some(config_dir().ok()?.join(icon))
+    }
+}
+
+/// How the games list is ordered, in the launcher and when printing it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortOrder {
+    /// Alphabetically by name.
+    #[default]
+    Name,
+    /// Most recently spoofed first, and the rest alphabetically.
+    Recent,
+    /// In the order the games list has them.
+    #[value(name = "none")]
+    Unsorted,
+}
+
+/// What the server told us about the cached games list, so the next fetch can be conditional.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+struct CacheValidators {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_response(url: &reqwest::Url, response: &reqwest::Response) -> Self {
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            url: url.to_string(),
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        }
+    }
+}
+
+/// Problems found in a games list. Errors stop it from loading at all, while warnings are
+/// things that load fine but probably aren't what was intended.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub entries: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Finds a game by name or alias, ignoring case and accents, so that "pokemon" finds "Pokémon".
+/// An exact match always wins, then a name that only differs in case and accents, then a name
+/// that contains it, as long as only one game fits.
+///
+/// # Errors
+///
+/// Fails if no game fits, or more than one does, in which case they're listed.
+pub fn find_game(games: &[Game], name: &str) -> anyhow::Result<Game> {
+    if let Some(game) = find_exact_game(games, name) {
+        return Ok(game.clone());
+    }
+    let candidates = loose_matches(games, name);
+    match candidates[..] {
+        [] => Err(no_game_named(games, name)),
+        [game] => Ok(game.clone()),
+        _ => anyhow::bail!(
+            "{name:?} could be more than one game, which did you mean?\n  {}",
+            list_names(&candidates)
+        ),
+    }
+}
+
+/// Finds a game by its exact name or one of its aliases, for when picking the wrong one would
+/// do damage, like removing it.
+///
+/// # Errors
+///
+/// Fails if no game is called exactly that, listing the ones [`find_game`] would have
+/// considered.
+pub fn find_game_exactly(games: &[Game], name: &str) -> anyhow::Result<Game> {
+    if let Some(game) = find_exact_game(games, name) {
+        return Ok(game.clone());
+    }
+    let candidates = loose_matches(games, name);
+    if candidates.is_empty() {
+        return Err(no_game_named(games, name));
+    }
+    anyhow::bail!(
+        "No game is called exactly {name:?}, did you mean one of these?\n  {}",
+        list_names(&candidates)
+    )
+}
+
+/// The game called `name`, going by its name first and then its aliases.
+fn find_exact_game<'a>(games: &'a [Game], name: &str) -> Option<&'a Game> {
+    games.iter().find(|game| game.name == name).or_else(|| {
+        games
+            .iter()
+            .find(|game| game.aliases.iter().any(|alias| alias == name))
+    })
+}
+
+/// Games whose name or an alias only differs from `name` in case and accents, or failing that
+/// contains it.
+fn loose_matches<'a>(games: &'a [Game], name: &str) -> Vec<&'a Game> {
+    let needle = normalize_name(name);
+    let normalized: Vec<(&Game, Vec<String>)> = games
+        .iter()
+        .map(|game| (game, game.names().map(normalize_name).collect()))
+        .collect();
+    let candidates: Vec<&Game> = normalized
+        .iter()
+        .filter(|(_, names)| names.contains(&needle))
+        .map(|&(game, _)| game)
+        .collect();
+    if !candidates.is_empty() {
+        return candidates;
+    }
+    normalized
+        .iter()
+        .filter(|(_, names)| names.iter().any(|name| name.contains(&needle)))
+        .map(|&(game, _)| game)
+        .collect()
+}
+
+/// Says there's no such game, along with some that there are. Games sharing a word with `name`
+/// come first, since a typo is the likeliest reason for getting here.
+fn no_game_named(games: &[Game], name: &str) -> anyhow::Error {
+    if games.is_empty() {
+        return anyhow::anyhow!("No game named {name:?}, the games list is empty");
+    }
+    let words: Vec<String> = normalize_name(name)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let related: Vec<&Game> = games
+        .iter()
+        .filter(|game| {
+            game.names().any(|own| {
+                normalize_name(own)
+                    .split_whitespace()
+                    .any(|word| words.iter().any(|wanted| wanted == word))
+            })
+        })
+        .collect();
+    if related.is_empty() {
+        let all: Vec<&Game> = games.iter().collect();
+        anyhow::anyhow!(
+            "No game named {name:?}, `--list` shows every game there is, such as:\n  {}",
+            list_names(&all)
+        )
+    } else {
+        anyhow::anyhow!(
+            "No game named {name:?}, did you mean one of these?\n  {}",
+            list_names(&related)
+        )
+    }
+}
+
+/// Up to [`MAX_SUGGESTIONS`] game names, one per line.
+fn list_names(games: &[&Game]) -> String {
+    let names: Vec<&str> = games
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|game| game.name.as_str())
+        .collect();
+    let more = games.len().saturating_sub(MAX_SUGGESTIONS);
+    if more > 0 {
+        format!("{}\n  and {more} more", names.join("\n  "))
+    } else {
+        names.join("\n  ")
+    }
+}
+
+/// Lowercase with accents and other non-ascii letters spelled out in plain ascii.
+fn normalize_name(name: &str) -> String {
+    deunicode::deunicode(name).to_lowercase()
+}
+
+/// Puts the games in the given order. Games that have never been spoofed count as the least
+/// recent, and so does everything if the history can't be read.
+pub fn sort_games(games: &mut [Game], order: SortOrder) {
+    match order {
+        SortOrder::Name => games.sort_by_cached_key(|game| game.name.to_lowercase()),
+        SortOrder::Recent => {
+            let last_played = last_played();
+            games.sort_by_cached_key(|game| {
+                (
+                    std::cmp::Reverse(last_played.get(&game.name).copied()),
+                    game.name.to_lowercase(),
+                )
+            });
+        }
+        SortOrder::Unsorted => {}
+    }
+}
+
+/// When each game was last spoofed, by name.
+fn last_played() -> HashMap<String, jiff::Timestamp> {
+    let history = crate::load_history().unwrap_or_else(|e| {
+        warn!("Failed to read the history, sorting by name instead: {e:#}");
+        Vec::new()
+    });
+    let mut last_played = HashMap::new();
+    // the history is oldest first, so later sessions overwrite earlier ones
+    for event in history {
+        if let HistoryEvent::Started { name, at, .. } = event {
+            last_played.insert(name, at);
+        }
+    }
+    last_played
+}
+
+/// Checks a profile name is something that can go in a file name, like `work` or `home-pc`.
+///
+/// # Errors
+///
+/// Fails if the name is empty or has anything but letters, digits, `-` and `_` in it.
+pub fn parse_profile_name(name: &str) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Profile names can only use letters, digits, - and _, got {name:?}"
+    );
+    Ok(name.to_string())
+}
+
+/// Switches to the games list of a named profile, `games.<name>.v2.json`, instead of the
+/// default `games.v2.json`. This has to happen before the games list is first used.
+///
+/// # Errors
+///
+/// Fails if the name isn't valid, or a profile has already been chosen.
+pub fn set_profile(name: &str) -> anyhow::Result<()> {
+    let name = parse_profile_name(name)?;
+    PROFILE
+        .set(name)
+        .map_err(|_| anyhow::anyhow!("A profile has already been chosen"))
+}
+
+/// The names of the profiles with a games list in the config directory, leaving out the
+/// default one.
+///
+/// # Errors
+///
+/// Fails if the config directory can't be read.
+pub fn list_profiles() -> anyhow::Result<Vec<String>> {
+    let dir = config_dir()?;
+    let mut profiles: Vec<String> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name().into_string().ok()?;
+                let name = file_name.strip_prefix("games.")?.strip_suffix(".v2.json")?;
+                parse_profile_name(name).ok()
+            })
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// The profile chosen with [`set_profile`], if any.
+#[must_use]
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+fn games_json_file_name() -> String {
+    PROFILE.get().map_or_else(
+        || "games.v2.json".to_string(),
+        |profile| format!("games.{profile}.v2.json"),
+    )
+}
+
+/// Where the games list of the current profile lives.
+///
+/// # Errors
+///
+/// Fails if the config directory can't be found or created.
+pub fn games_json_path() -> anyhow::Result<PathBuf> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push(games_json_file_name());
+    Ok(path)
+}
+
+/// Where the games list is copied to before a download replaces it.
+///
+/// # Errors
+///
+/// Fails if the config directory can't be found or created.
+pub fn games_backup_path() -> anyhow::Result<PathBuf> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push(format!("{}.bak", games_json_file_name()));
+    Ok(path)
+}
+
+/// Copies the local games list to [`games_backup_path`], unless it already matches `games`,
+/// in which case the backup is left alone rather than being replaced with the same list.
+fn backup_games_json(games: &[Game]) -> anyhow::Result<()> {
+    let path = games_json_path()?;
+    let current = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    if parse_games(current.as_bytes()).is_ok_and(|(current, _)| current == games) {
+        return Ok(());
+    }
+    let backup = games_backup_path()?;
+    write_atomically(&backup, |file| Ok(file.write_all(current.as_bytes())?))?;
+    debug!("Backed up the games list to {}", backup.display());
+    Ok(())
+}
+
+/// Swaps the backup made before the last download with the current games list, so running it
+/// again undoes it.
+///
+/// # Errors
+///
+/// Fails if there is no backup or the files can't be moved.
+pub fn restore_games_backup() -> anyhow::Result<()> {
+    let _lock = lock_games_json()?;
+    let path = games_json_path()?;
+    let backup = games_backup_path()?;
+    anyhow::ensure!(
+        backup.exists(),
+        "There is no backup at {}",
+        backup.display()
+    );
+    if !path.exists() {
+        return Ok(fs::rename(&backup, &path)?);
+    }
+    let mut temp = backup.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    fs::rename(&backup, &temp)?;
+    fs::rename(&path, &backup)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+/// Held while the games list is being written so that two launchers started at once don't
+/// interleave their writes. Waits for the other one to finish rather than giving up, since
+/// nothing holds it for longer than a write takes. The lock goes when the file is dropped.
+fn lock_games_json() -> anyhow::Result<File> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push(format!("{}.lock", games_json_file_name()));
+    let file = File::create(&path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => {
+            info!("Another doppelgamer is writing the games list, waiting for it");
+            file.lock()?;
+        }
+        Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+    }
+    Ok(file)
+}
+
+fn cache_validators_path() -> anyhow::Result<PathBuf> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push(format!("{}.etag", games_json_file_name()));
+    Ok(path)
+}
+
+fn load_cache_validators(url: &reqwest::Url) -> Option<CacheValidators> {
+    let data = fs::read_to_string(cache_validators_path().ok()?).ok()?;
+    let validators: CacheValidators = serde_json::from_str(&data).ok()?;
+    // validators from some other games url mean nothing to this one
+    (validators.url == url.as_str()).then_some(validators)
+}
+
+fn save_cache_validators(validators: &CacheValidators) -> anyhow::Result<()> {
+    write_atomically(&cache_validators_path()?, |file| {
+        Ok(serde_json::to_writer(file, validators)?)
+    })
+}
+
+/// Records which release's bundled games were last merged into the local cache, so that games
+/// removed by hand don't come back on every launch.
+fn bundled_marker_path() -> anyhow::Result<PathBuf> {
+    let mut path = config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push(format!("{}.bundled", games_json_file_name()));
+    Ok(path)
+}
+
+fn ensure_local_games_json() {
+    info!("Ensuring local games.json exists");
+    let Ok(path) = games_json_path() else {
+        warn!("Failed to determine games.json path, skipping local cache");
+        return;
+    };
+    let marker = bundled_marker_path().ok();
+
+    if !path.exists() {
+        let written = write_atomically(&path, |file| Ok(file.write_all(BUNDLED_GAMES.as_bytes())?));
+        if written.is_ok() {
+            info!("Wrote bundled games.json to local cache");
+            // the bundled list is not what the validators describe, so the next fetch must be a
+            // full one
+            if let Ok(path) = cache_validators_path() {
+                let _ = fs::remove_file(path);
+            }
+            if let Some(marker) = &marker {
+                let _ = fs::write(marker, env!("CARGO_PKG_VERSION"));
+            }
+        }
+        return;
+    }
+
+    let merged_version = marker.as_ref().and_then(|m| fs::read_to_string(m).ok());
+    if merged_version.as_deref() != Some(env!("CARGO_PKG_VERSION")) {
+        match merge_bundled_games(&path) {
+            Ok(()) => {
+                if let Some(marker) = &marker {
+                    let _ = fs::write(marker, env!("CARGO_PKG_VERSION"));
+                }
+            }
+            Err(e) => warn!("Failed to merge bundled games into local cache: {e}"),
+        }
+    }
+}
+
+/// Adds any bundled games missing from the local cache, leaving existing entries alone.
+fn merge_bundled_games(path: &Path) -> anyhow::Result<()> {
+    let (mut games, _) = parse_games(&fs::read(path)?)?;
+    let (bundled, _) = parse_games(BUNDLED_GAMES.as_bytes())?;
+
+    let known: HashSet<String> = games.iter().map(|game| game.name.clone()).collect();
+    let before = games.len();
+    games.extend(
+        bundled
+            .into_iter()
+            .filter(|game| !known.contains(&game.name)),
+    );
+
+    let added = games.len() - before;
+    if added > 0 {
+        save_games(&games)?;
+        info!("Merged {added} bundled games into local cache");
+    }
+    Ok(())
+}
+
+/// Reads the local games list, creating it from the bundled copy on first use and replacing it
+/// with the bundled copy if it can't be read.
+///
+/// # Panics
+///
+/// Only if the games list built into the binary is broken.
+#[must_use]
+#[tracing::instrument]
+pub fn load_games() -> Vec<Game> {
+    // the bundled list may be written or merged in, so that has to wait its turn too
+    let _lock = lock_games_json().inspect_err(|e| warn!("Failed to lock the games list: {e:#}"));
+    read_games_list()
+}
+
+/// The games list, locked until this is dropped so that nothing else can write it in between
+/// loading it and saving changes to it.
+pub struct LockedGames {
+    _lock: File,
+}
+
+impl LockedGames {
+    /// Reads the local games list the same way as [`load_games`].
+    ///
+    /// # Panics
+    ///
+    /// Only if the games list built into the binary is broken.
+    #[must_use]
+    pub fn load(&self) -> Vec<Game> {
+        read_games_list()
+    }
+
+    /// Replaces the local games list.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the list can't be written.
+    pub fn save(&self, games: &[Game]) -> anyhow::Result<()> {
+        save_games(games)
+    }
+}
+
+/// Locks the games list for a change that has to read it first, like adding a game.
+///
+/// # Errors
+///
+/// Fails if the lock file can't be created or locked.
+pub fn lock_games() -> anyhow::Result<LockedGames> {
+    Ok(LockedGames {
+        _lock: lock_games_json()?,
+    })
+}
+
+/// [`load_games`] without taking the lock, for callers that already hold it.
+fn read_games_list() -> Vec<Game> {
+    info!("Loading games from local cache");
+    if let Ok(path) = games_json_path() {
+        debug!("Local games list is at {}", path.display());
+    }
+    // a corrupt cache is moved aside and rewritten from the bundled list, then tried once more
+    for retry in [false, true] {
+        ensure_local_games_json();
+        let Some(path) = games_json_path().ok() else {
+            break;
+        };
+        let Ok(data) = fs::read_to_string(&path) else {
+            break;
+        };
+        match parse_games(data.as_bytes()) {
+            Ok((games, version)) => {
+                if version < GAMES_SCHEMA_VERSION {
+                    info!("Upgrading the local games list from version {version}");
+                    if let Err(e) = save_games(&games) {
+                        warn!("Failed to write the upgraded games list: {e:#}");
+                    }
+                }
+                return games;
+            }
+            Err(e) => {
+                warn!("Local games.json is invalid: {e:#}");
+                for problem in validate_games(&data).errors {
+                    warn!("{problem}");
+                }
+                if retry || !set_aside_corrupt_games_json(&path) {
+                    break;
+                }
+            }
+        }
+    }
+    warn!("Using bundled games instead");
+    parse_games(BUNDLED_GAMES.as_bytes())
+        .expect("bundled games json should be valid")
+        .0
+}
+
+/// Moves a broken games list out of the way so it can be replaced, keeping it around in case
+/// it had hand made changes worth rescuing.
+fn set_aside_corrupt_games_json(path: &Path) -> bool {
+    let mut corrupt = path.as_os_str().to_owned();
+    corrupt.push(".corrupt");
+    let corrupt = PathBuf::from(corrupt);
+    match fs::rename(path, &corrupt) {
+        Ok(()) => {
+            warn!(
+                "Moved the broken games list to {}, replacing it with the bundled one",
+                corrupt.display()
+            );
+            true
+        }
+        Err(e) => {
+            warn!("Failed to move the broken games list aside: {e}");
+            false
+        }
+    }
+}
+
+/// Fetches the latest games list unless `use_local` is set, falling back to the local copy.
+/// This blocks until the fetch is done, so async callers want [`fetch_remote_games`] instead.
+#[must_use]
+pub fn get_games(use_local: bool, urls: Vec<reqwest::Url>, retries: u32) -> Vec<Game> {
+    if !use_local
+        && let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        && let Some(games) = runtime.block_on(fetch_remote_games(urls, retries, false))
+    {
+        return games;
+    }
+    load_games()
+}
+
+/// Checks a games list entry by entry, so that problems can be pinned to the entry and field
+/// at fault rather than a line and column.
+#[must_use]
+pub fn validate_games(data: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let entries = match serde_json::from_str(data) {
+        Ok(serde_json::Value::Array(entries)) => entries,
+        Ok(serde_json::Value::Object(mut file)) if file.contains_key("games") => {
+            match file.get("version").and_then(serde_json::Value::as_u64) {
+                Some(version) if version <= u64::from(GAMES_SCHEMA_VERSION) => {}
+                Some(version) => report.errors.push(format!(
+                    "version {version} is newer than this doppelgamer understands"
+                )),
+                None => report
+                    .errors
+                    .push("`version` must be a whole number".to_string()),
+            }
+            match file.remove("games") {
+                Some(serde_json::Value::Array(entries)) => entries,
+                _ => {
+                    report
+                        .errors
+                        .push("`games` must be a json array".to_string());
+                    return report;
+                }
+            }
+        }
+        Ok(_) => {
+            report.errors.push(
+                "games list must be a json array, or an object with a `games` array".to_string(),
+            );
+            return report;
+        }
+        Err(e) => {
+            report.errors.push(format!("invalid json: {e}"));
+            return report;
+        }
+    };
+    report.entries = entries.len();
+
+    let mut seen: [HashMap<&str, usize>; 2] = Default::default();
+    // names and aliases share one namespace, since either can be used to pick a game. the
+    // bool is whether it was an alias, as duplicate names are already caught above
+    let mut seen_names: HashMap<String, (usize, bool)> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(entry) = entry.as_object() else {
+            report
+                .errors
+                .push(format!("entry {index} is not an object"));
+            continue;
+        };
+        for (field, seen) in ["name", "exe"].into_iter().zip(&mut seen) {
+            match entry.get(field) {
+                None => report
+                    .errors
+                    .push(format!("entry {index} missing `{field}`")),
+                Some(serde_json::Value::String(value))
+                    if field == "exe" && !is_relative_exe(Path::new(value)) =>
+                {
+                    report.errors.push(format!(
+                        "entry {index} `exe` must be a relative path inside the data directory: {value:?}"
+                    ));
+                }
+                Some(serde_json::Value::String(value)) => {
+                    if field == "exe"
+                        && let Some(problem) = windows_file_name_problem(Path::new(value))
+                    {
+                        report.warnings.push(format!(
+                            "entry {index} `exe` won't work on windows, {problem}"
+                        ));
+                    }
+                    match seen.entry(value) {
+                        Entry::Occupied(first) => report.warnings.push(format!(
+                            "entry {index} has the same `{field}` as entry {}: {value:?}",
+                            first.get()
+                        )),
+                        Entry::Vacant(slot) => {
+                            slot.insert(index);
+                        }
+                    }
+                }
+                Some(_) => report
+                    .errors
+                    .push(format!("entry {index} `{field}` must be a string")),
+            }
+        }
+        for field in ["icon", "rpc_image"] {
+            match entry.get(field) {
+                None | Some(serde_json::Value::String(_)) => {}
+                Some(_) => report
+                    .errors
+                    .push(format!("entry {index} `{field}` must be a string")),
+            }
+        }
+        match entry.get("local") {
+            None | Some(serde_json::Value::Bool(_)) => {}
+            Some(_) => report
+                .errors
+                .push(format!("entry {index} `local` must be true or false")),
+        }
+        match entry.get("tags") {
+            None => {}
+            Some(serde_json::Value::Array(tags))
+                if tags.iter().all(serde_json::Value::is_string) => {}
+            Some(_) => report
+                .errors
+                .push(format!("entry {index} `tags` must be a list of strings")),
+        }
+        let name = entry.get("name").and_then(serde_json::Value::as_str);
+        let aliases = match entry.get("aliases") {
+            None => Vec::new(),
+            Some(serde_json::Value::Array(aliases))
+                if aliases.iter().all(serde_json::Value::is_string) =>
+            {
+                aliases
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .collect()
+            }
+            Some(_) => {
+                report
+                    .errors
+                    .push(format!("entry {index} `aliases` must be a list of strings"));
+                Vec::new()
+            }
+        };
+        let names = name.into_iter().map(|name| (name, false));
+        for (name, is_alias) in names.chain(aliases.into_iter().map(|alias| (alias, true))) {
+            match seen_names.entry(normalize_name(name)) {
+                Entry::Occupied(first) if first.get().0 != index && (is_alias || first.get().1) => {
+                    report.warnings.push(format!(
+                        "entry {index} has a name or alias already used by entry {}: {name:?}",
+                        first.get().0
+                    ));
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(slot) => {
+                    slot.insert((index, is_alias));
+                }
+            }
+        }
+    }
+    report
+}
+
+/// Replaces the local games list, in the layout of [`GAMES_SCHEMA_VERSION`].
+///
+/// # Errors
+///
+/// Fails if the list can't be written.
+pub fn save_games(games: &[Game]) -> anyhow::Result<()> {
+    let file_contents = VersionedGames {
+        version: GAMES_SCHEMA_VERSION,
+        games,
+    };
+    write_atomically(&games_json_path()?, |file| {
+        Ok(serde_json::to_writer_pretty(file, &file_contents)?)
+    })
+}
+
+/// What came of asking one mirror for the games list.
+enum Fetched {
+    Games(Vec<Game>, CacheValidators),
+    NotModified,
+    Failed,
+}
+
+/// The downloaded list, with the games marked [`Game::local`] put back in. Everything else
+/// goes by the download, so a game it has dropped or renamed is gone from the local list too.
+/// A local game with the same name as a downloaded one takes its place, since that's what
+/// `add-game --overwrite` is for.
+fn merge_remote_games(remote: Vec<Game>, local: Vec<Game>) -> Vec<Game> {
+    let mut added: Vec<Game> = local.into_iter().filter(|game| game.local).collect();
+    let mut games: Vec<Game> = remote
+        .into_iter()
+        .map(
+            |game| match added.iter().position(|own| own.name == game.name) {
+                Some(index) => added.remove(index),
+                None => Game {
+                    local: false,
+                    ..game
+                },
+            },
+        )
+        .collect();
+    games.extend(added);
+    games
+}
+
+/// Tries each url in turn until one of them hands over the games list, which is then merged
+/// into the local cache with [`merge_remote_games`]. Returns `None` when every one of them fails or the cached copy is already up to
+/// date. With `fresh` the cache validators aren't sent, so the whole list comes back even if
+/// the server thinks the cached copy is current.
+pub async fn fetch_remote_games(
+    urls: Vec<reqwest::Url>,
+    retries: u32,
+    fresh: bool,
+) -> Option<Vec<Game>> {
+    let started = std::time::Instant::now();
+    let client = match reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Failed to create http client: {e}");
+            return None;
+        }
+    };
+    let count = urls.len();
+    for url in urls {
+        let (games, validators) = match fetch_from(&client, &url, retries, fresh).await {
+            Fetched::Games(games, validators) => (games, validators),
+            Fetched::NotModified => return None,
+            Fetched::Failed => continue,
+        };
+        info!("Fetched {} games from {url}", games.len());
+        debug!("Fetching the games list took {:?}", started.elapsed());
+
+        let _lock =
+            lock_games_json().inspect_err(|e| warn!("Failed to lock the games list: {e:#}"));
+        let local = read_games_list();
+        let dropped = local
+            .iter()
+            .filter(|game| !game.local && !games.iter().any(|new| new.name == game.name))
+            .count();
+        if dropped > 0 {
+            info!("Dropped {dropped} games the download no longer has");
+        }
+        let games = merge_remote_games(games, local);
+        if let Err(e) = backup_games_json(&games) {
+            warn!("Failed to back up the local games list: {e:#}");
+        }
+        match save_games(&games) {
+            Ok(()) => {
+                if let Err(e) = save_cache_validators(&validators) {
+                    warn!("Failed to save games.json cache validators: {e}");
+                }
+            }
+            Err(e) => error!("Failed to write fetched games to local cache: {e}"),
+        }
+        return Some(games);
+    }
+    if count > 1 {
+        warn!("None of the {count} games list urls worked");
+    }
+    warn!("Offline, using cached games list");
+    None
+}
+
+#[tracing::instrument(skip_all, fields(%url))]
+async fn fetch_from(
+    client: &reqwest::Client,
+    url: &reqwest::Url,
+    retries: u32,
+    fresh: bool,
+) -> Fetched {
+    info!("Fetching games");
+    let validators = if fresh {
+        None
+    } else {
+        load_cache_validators(url)
+    };
+    let mut attempt = 0;
+    let response = loop {
+        let mut request = client.get(url.clone());
+        if let Some(validators) = &validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        match request.send().await {
+            Ok(r) => break r,
+            Err(e) if e.is_connect() || e.is_timeout() => {
+                if attempt >= retries {
+                    warn!("Failed to reach {url}, giving up");
+                    return Fetched::Failed;
+                }
+                attempt += 1;
+                let delay = RETRY_BASE_DELAY.saturating_mul(2_u32.saturating_pow(attempt - 1));
+                warn!(
+                    "Failed to reach {url}, retrying in {}s (attempt {attempt} of {retries})",
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                warn!("Failed to fetch remote games.json: {e}");
+                return Fetched::Failed;
+            }
+        }
+    };
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        info!("Remote games.json not modified, keeping local cache");
+        return Fetched::NotModified;
+    }
+    if !response.status().is_success() {
+        warn!(
+            "Failed to fetch remote games.json, status: {}",
+            response.status()
+        );
+        return Fetched::Failed;
+    }
+
+    let validators = CacheValidators::from_response(url, &response);
+    let body = match read_body(response).await {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to download remote games.json: {e}");
+            return Fetched::Failed;
+        }
+    };
+    info!("Remote games.json fetched, parsing response");
+    match parse_games(&body) {
+        Ok((games, _)) => Fetched::Games(games, validators),
+        Err(e) => {
+            warn!("Failed to parse remote games.json: {e:#}");
+            Fetched::Failed
+        }
+    }
+}
+
+/// Reads the body a chunk at a time so that a slow download can show it's getting somewhere.
+async fn read_body(mut response: reqwest::Response) -> reqwest::Result<Vec<u8>> {
+    let mut progress = Progress::new(response.content_length());
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        progress.update(body.len() as u64);
+    }
+    progress.finish();
+    Ok(body)
+}
+
+/// How long a download can take before its progress is shown. The games list usually arrives
+/// well within it, and then nothing is printed at all.
+const PROGRESS_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// A one line progress indicator on stderr, redrawn in place. It stays quiet unless stderr is a
+/// terminal and info messages are being logged, so `--quiet` and redirected output never see it.
+struct Progress {
+    total: Option<u64>,
+    started: std::time::Instant,
+    enabled: bool,
+    /// Length of the line last drawn, so the next one can cover it up completely.
+    drawn: usize,
+    frame: usize,
+}
+
+impl Progress {
+    fn new(total: Option<u64>) -> Self {
+        Self {
+            total: total.filter(|&total| total > 0),
+            started: std::time::Instant::now(),
+            enabled: std::io::stderr().is_terminal() && tracing::enabled!(tracing::Level::INFO),
+            drawn: 0,
+            frame: 0,
+        }
+    }
+
+    fn update(&mut self, done: u64) {
+        if !self.enabled || self.started.elapsed() < PROGRESS_DELAY {
+            return;
+        }
+        let line = match self.total {
+            Some(total) => format!(
+                "Downloading games list: {}% ({} of {} KiB)",
+                done.min(total) * 100 / total,
+                done / 1024,
+                total.div_ceil(1024)
+            ),
+            // no length to go by, so just show that bytes are still coming in
+            None => {
+                const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+                self.frame = (self.frame + 1) % SPINNER.len();
+                format!(
+                    "Downloading games list: {} {} KiB",
+                    SPINNER[self.frame],
+                    done / 1024
+                )
+            }
+        };
+        self.draw(&line);
+    }
+
+    fn finish(&mut self) {
+        if self.drawn > 0 {
+            self.draw("");
+            eprint!("\r");
+        }
+    }
+
+    fn draw(&mut self, line: &str) {
+        let mut stderr = std::io::stderr().lock();
+        // a failed write to a terminal isn't worth failing the download over
+        let _ = write!(stderr, "\r{line:<width$}", width = self.drawn);
+        let _ = stderr.flush();
+        self.drawn = line.len();
+    }
+}
+
+/// Whether an exe path only goes down into directories, so joining it onto the data directory
+/// can't end up somewhere else. Exe paths are always relative to the data directory, and
+/// absolute paths are never honoured.
+///
+/// The same games list is used everywhere, so this goes by what windows would make of the
+/// path as well as what this platform would. Otherwise `C:\games\x.exe` or `\x.exe` would pass
+/// as a plain file name when checked on linux, and then replace the data directory when joined
+/// onto it on windows.
+#[must_use]
+pub fn is_relative_exe(exe: &Path) -> bool {
+    let native = exe.file_name().is_some()
+        && exe.components().all(|component| {
+            matches!(
+                component,
+                std::path::Component::Normal(_) | std::path::Component::CurDir
+            )
+        });
+    let text = exe.to_string_lossy();
+    let rooted = text.starts_with(['/', '\\']);
+    // a drive letter, with or without a separator after it, makes it absolute or relative to
+    // that drive's own current directory, neither of which is inside the data directory
+    let drive = text.as_bytes().get(1) == Some(&b':');
+    let mut parts = text.split(['/', '\\']);
+    let climbs = parts.any(|part| part == "..");
+    native && !rooted && !drive && !climbs
+}
+
+/// Finds anything in an exe path that Windows can't use as a file name, such as reserved
+/// characters, a trailing dot or a device name like `CON`.
+///
+/// Any extension will do, not just `.exe`: Discord goes by the name of the running process, and
+/// some games really do run as `game.dat` or `gameguard.des`, so the bundled list has entries
+/// like that. Windows starts the spoofer under those names all the same. Only a missing
+/// extension is a problem, since then Windows looks for the name with `.exe` added instead.
+#[must_use]
+pub fn windows_file_name_problem(exe: &Path) -> Option<String> {
+    const RESERVED: [&str; 6] = ["CON", "PRN", "AUX", "NUL", "CONIN$", "CONOUT$"];
+    for component in exe.components() {
+        let std::path::Component::Normal(part) = component else {
+            continue;
+        };
+        let part = part.to_string_lossy();
+        if let Some(c) = part
+            .chars()
+            .find(|&c| c.is_control() || r#":*?"<>|"#.contains(c))
+        {
+            return Some(format!("{part:?} contains {c:?}"));
+        }
+        if part.ends_with(['.', ' ']) {
+            return Some(format!("{part:?} ends with a dot or space"));
+        }
+        let stem = part
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let is_numbered_device = (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.len() == 4
+            && stem.ends_with(|c: char| c.is_ascii_digit());
+        if RESERVED.contains(&stem.as_str()) || is_numbered_device {
+            return Some(format!("{part:?} is a reserved device name"));
+        }
+    }
+    if exe.extension().is_none() {
+        return Some("it has no file extension, like .exe".to_string());
+    }
+    None
+}
+
+/// Where games that share an exe with an earlier entry get their own copies, under a folder
+/// named after a hash of the game's name.
+const SHARED_EXE_DIR: &str = "same-exe";
+
+/// Gives every game after the first one with a given exe its own path, so that launching one of
+/// them can't replace or close another's spoofer. The exe's name and the folders it's in stay as
+/// they are, since those are what Discord goes by, and only a folder is added in front.
+pub fn separate_shared_exes(games: &mut [Game]) {
+    let mut first_with: HashMap<String, String> = HashMap::new();
+    for game in games {
+        let mut key = game.exe.to_string_lossy().into_owned();
+        // windows paths don't care about case, so these would be the same file there
+        if cfg!(windows) {
+            key = key.to_lowercase();
+        }
+        match first_with.entry(key) {
+            Entry::Vacant(slot) => {
+                slot.insert(game.name.clone());
+            }
+            Entry::Occupied(first) => {
+                let exe = Path::new(SHARED_EXE_DIR)
+                    .join(format!("{:016x}", name_hash(&game.name)))
+                    .join(&game.exe);
+                warn!(
+                    "{} has the same exe as {}, giving it its own copy at {}",
+                    game.name,
+                    first.get(),
+                    exe.display()
+                );
+                game.exe = exe;
+            }
+        }
+    }
+}
+
+/// FNV-1a, which unlike the standard library's hasher is guaranteed to give the same answer
+/// from one release to the next, so a game's copy stays put.
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Works out where the spoofer copy for a game goes under `data_dir`, without touching the
+/// disk. The exe comes straight from the games list, so anything that would land outside the
+/// data directory is rejected.
+///
+/// # Errors
+///
+/// Fails if the exe path escapes the data directory or can't be used as a file name.
+pub fn exe_path_in(data_dir: &Path, game: &Game) -> anyhow::Result<PathBuf> {
+    if !is_relative_exe(&game.exe) {
+        anyhow::bail!(
+            "Refusing to use exe path {} for {}, it must stay inside the data directory",
+            game.exe.display(),
+            game.name
+        );
+    }
+    if cfg!(windows)
+        && let Some(problem) = windows_file_name_problem(&game.exe)
+    {
+        anyhow::bail!(
+            "Can't use exe path {} for {}: {problem}",
+            game.exe.display(),
+            game.name
+        );
+    }
+    Ok(data_dir.join(&game.exe))
+}
+
+/// Like [`exe_path_in`] the data directory, but also creates the directories the copy goes in,
+/// ready for writing it.
+///
+/// # Errors
+///
+/// Fails as [`exe_path_in`] does, or if the directories can't be created or resolve outside the
+/// data directory.
+pub fn game_exe_path(game: &Game) -> anyhow::Result<PathBuf> {
+    let data_dir = data_dir()?;
+    let path = exe_path_in(&data_dir, game)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+        // catch symlinked directories pointing elsewhere too
+        if !fs::canonicalize(parent)?.starts_with(fs::canonicalize(&data_dir)?) {
+            anyhow::bail!(
+                "Refusing to use exe path {} for {}, it resolves outside the data directory",
+                game.exe.display(),
+                game.name
+            );
+        }
+    }
+    debug!("Using spoofer path: {}", path.display());
+    Ok(path)
+}
+
+/// Works out, without creating anything, whether [`game_exe_path`] and writing the spoofer copy
+/// would go wrong for a game, so a broken entry can be pointed out before it's picked.
+#[must_use]
+pub fn exe_problem(game: &Game) -> Option<String> {
+    if !is_relative_exe(&game.exe) {
+        return Some(
+            "the exe path must be relative to the data directory and stay inside it".to_string(),
+        );
+    }
+    if cfg!(windows)
+        && let Some(problem) = windows_file_name_problem(&game.exe)
+    {
+        return Some(problem);
+    }
+    let data_dir = match data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return Some(format!("{e:#}")),
+    };
+    let path = data_dir.join(&game.exe);
+    if path.is_dir() {
+        return Some(format!("{} is a directory", path.display()));
+    }
+    // whatever part of the path exists already has to be a directory that can be written to
+    let existing = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.exists())?;
+    if !existing.is_dir() {
+        return Some(format!("{} is a file, not a directory", existing.display()));
+    }
+    if existing
+        .metadata()
+        .is_ok_and(|meta| meta.permissions().readonly())
+    {
+        return Some(format!("{} is read only", existing.display()));
+    }
+    if existing.starts_with(&data_dir)
+        && let (Ok(resolved), Ok(data_dir)) =
+            (fs::canonicalize(existing), fs::canonicalize(&data_dir))
+        && !resolved.starts_with(data_dir)
+    {
+        return Some("the exe path resolves outside the data directory".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(exe: &str) -> Game {
+        Game {
+            name: "Test Game".to_string(),
+            exe: PathBuf::from(exe),
+            tags: Vec::new(),
+            aliases: Vec::new(),
+            icon: None,
+            rpc_image: None,
+            local: false,
+        }
+    }
+
+    fn named(name: &str, local: bool) -> Game {
+        Game {
+            name: name.to_string(),
+            local,
+            ..game(&format!("{name}.exe"))
+        }
+    }
+
+    fn names(games: &[Game]) -> Vec<&str> {
+        games.iter().map(|game| game.name.as_str()).collect()
+    }
+
+    #[test]
+    fn games_removed_upstream_go_away() {
+        let local = vec![named("Kept", false), named("Removed", false)];
+        let merged = merge_remote_games(vec![named("Kept", false)], local);
+        assert_eq!(names(&merged), ["Kept"]);
+    }
+
+    #[test]
+    fn games_renamed_upstream_are_not_doubled() {
+        let local = vec![named("Old Name", false)];
+        let merged = merge_remote_games(vec![named("New Name", false)], local);
+        assert_eq!(names(&merged), ["New Name"]);
+    }
+
+    #[test]
+    fn added_games_survive_downloads() {
+        let local = vec![named("Upstream", false), named("Mine", true)];
+        let merged = merge_remote_games(vec![named("Upstream", false)], local);
+        assert_eq!(names(&merged), ["Upstream", "Mine"]);
+        assert!(merged[1].local);
+    }
+
+    #[test]
+    fn overwritten_games_keep_the_local_version() {
+        let mine = Game {
+            exe: PathBuf::from("mine/game.exe"),
+            ..named("Game", true)
+        };
+        let merged = merge_remote_games(vec![named("Game", false)], vec![mine.clone()]);
+        assert_eq!(merged, [mine]);
+    }
+
+    #[test]
+    fn downloads_cant_mark_games_local() {
+        let merged = merge_remote_games(vec![named("Game", true)], Vec::new());
+        assert!(!merged[0].local);
+    }
+
+    #[test]
+    fn relative_exes_are_accepted() {
+        for exe in ["game.exe", "bin/game.exe", "bin\\game.exe", "./game.exe"] {
+            assert!(is_relative_exe(Path::new(exe)), "{exe}");
+        }
+    }
+
+    #[test]
+    fn climbing_out_is_refused() {
+        for exe in [
+            "../game.exe",
+            "bin/../../game.exe",
+            "bin\\..\\..\\game.exe",
+            "..",
+        ] {
+            assert!(!is_relative_exe(Path::new(exe)), "{exe}");
+        }
+    }
+
+    #[test]
+    fn drive_letters_are_refused() {
+        for exe in ["C:\\Games\\game.exe", "C:/Games/game.exe", "C:game.exe"] {
+            assert!(!is_relative_exe(Path::new(exe)), "{exe}");
+        }
+    }
+
+    #[test]
+    fn absolute_and_unc_paths_are_refused() {
+        for exe in [
+            "/usr/bin/game",
+            "\\game.exe",
+            "\\\\server\\share\\game.exe",
+            "//server/share/game.exe",
+            "",
+        ] {
+            assert!(!is_relative_exe(Path::new(exe)), "{exe}");
+        }
+    }
+
+    #[test]
+    fn exe_path_goes_under_the_data_dir() {
+        let data_dir = Path::new("data");
+        let path = exe_path_in(data_dir, &game("bin/game.exe")).expect("path should be fine");
+        assert_eq!(path, data_dir.join("bin/game.exe"));
+    }
+
+    #[test]
+    fn exe_path_outside_the_data_dir_is_refused() {
+        for exe in ["../game.exe", "/game.exe", "C:\\game.exe"] {
+            assert!(exe_path_in(Path::new("data"), &game(exe)).is_err(), "{exe}");
+        }
+    }
+
+    #[test]
+    fn reserved_names_are_problems() {
+        for exe in [
+            "con.exe",
+            "bin/NUL.exe",
+            "COM1.exe",
+            "CONIN$.exe",
+            "conout$.exe",
+        ] {
+            assert!(windows_file_name_problem(Path::new(exe)).is_some(), "{exe}");
+        }
+        assert!(windows_file_name_problem(Path::new("console.exe")).is_none());
+    }
+
+    #[test]
+    fn any_extension_will_do() {
+        for exe in ["game.exe", "game.dat", "data/gameguard.des"] {
+            assert!(windows_file_name_problem(Path::new(exe)).is_none(), "{exe}");
+        }
+        assert!(windows_file_name_problem(Path::new("game")).is_some());
+        assert!(windows_file_name_problem(Path::new("game.")).is_some());
+        assert!(windows_file_name_problem(Path::new("ga?me.exe")).is_some());
+    }
+
+    #[test]
+    fn bare_lists_are_version_0() {
+        let (games, version) =
+            parse_games(br#"[{"name": "Test Game", "exe": "game.exe"}]"#).expect("should parse");
+        assert_eq!(version, 0);
+        assert_eq!(games, [game("game.exe")]);
+    }
+
+    #[test]
+    fn versioned_lists_keep_their_version() {
+        let data = br#"{"version": 1, "games": [{"name": "Test Game", "exe": "game.exe"}]}"#;
+        let (games, version) = parse_games(data).expect("should parse");
+        assert_eq!(version, 1);
+        assert_eq!(games, [game("game.exe")]);
+    }
+
+    #[test]
+    fn newer_lists_are_refused() {
+        let data = format!(
+            r#"{{"version": {}, "games": []}}"#,
+            GAMES_SCHEMA_VERSION + 1
+        );
+        assert!(parse_games(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn saved_layout_parses_back() {
+        let games = [game("bin/game.exe")];
+        let data = serde_json::to_vec(&VersionedGames {
+            version: GAMES_SCHEMA_VERSION,
+            games: &games,
+        })
+        .expect("should serialize");
+        assert_eq!(
+            parse_games(&data).expect("should parse"),
+            (games.to_vec(), GAMES_SCHEMA_VERSION)
+        );
+    }
+}
@@ -0,0 +1,670 @@
+//! The parts of doppelgamer that aren't tied to a window, shared by the launcher and the
+//! spoofer and usable from other programs too.
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, IsTerminal as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use etcetera::AppStrategy as _;
+use jiff::{Span, SpanRelativeTo, SpanRound, Unit};
+use tracing::{Level, warn};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::MakeWriterExt as _;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+pub mod config;
+pub mod games;
+pub mod spoofers;
+
+pub use games::Game;
+
+static SPOOFER_BIN: OnceLock<&'static [u8]> = OnceLock::new();
+
+/// Hands over the spoofer binary for [`launch_spoofer`] to install. The library can't embed it
+/// itself, since the spoofer is built from the library, so each would keep leaving the other
+/// out of date. Programs using the library embed their own build of `src/bin/spoofer.rs`, the
+/// way the launcher does, and set it once at startup.
+///
+/// # Errors
+///
+/// Fails if it has already been set.
+pub fn set_spoofer_bin(spoofer_bin: &'static [u8]) -> anyhow::Result<()> {
+    SPOOFER_BIN
+        .set(spoofer_bin)
+        .map_err(|_| anyhow::anyhow!("The spoofer binary has already been set"))
+}
+
+/// The binary given to [`set_spoofer_bin`], if it has been.
+#[must_use]
+pub fn spoofer_bin() -> Option<&'static [u8]> {
+    SPOOFER_BIN.get().copied()
+}
+
+/// Fetches the latest games list from [`games::GAMES_JSON_URL`] unless `use_local` is set,
+/// falling back to the local copy. Like [`games::get_games`], this blocks until the fetch is done.
+///
+/// # Errors
+///
+/// Fails if the default url doesn't parse. A fetch that doesn't work out just means the local
+/// copy is used.
+pub fn load_games(use_local: bool) -> anyhow::Result<Vec<Game>> {
+    let url = reqwest::Url::parse(games::GAMES_JSON_URL)?;
+    Ok(games::get_games(
+        use_local,
+        vec![url],
+        games::DEFAULT_FETCH_RETRIES,
+    ))
+}
+
+/// Starts a spoofer for `game` that runs for `minutes`, with the same settings as the launcher
+/// without any flags. [`spoofers::spawn_spoofer`] takes settings of its own.
+///
+/// # Errors
+///
+/// Fails if no binary was given to [`set_spoofer_bin`], the duration is zero or longer than
+/// [`MAX_DURATION_MINUTES`], or the spoofer can't be written or started.
+pub fn launch_spoofer(game: &Game, minutes: u64) -> anyhow::Result<std::process::Child> {
+    let spoofer_bin = spoofer_bin().context("No spoofer binary has been set")?;
+    let minutes = u32::try_from(minutes)
+        .ok()
+        .filter(|minutes| (1..=MAX_DURATION_MINUTES).contains(minutes))
+        .with_context(|| {
+            format!("Duration must be between 1 and {MAX_DURATION_MINUTES} minutes, got {minutes}")
+        })?;
+    spoofers::spawn_spoofer(
+        game,
+        minutes,
+        &spoofers::SpooferOptions::default(),
+        false,
+        spoofer_bin,
+    )
+}
+
+/// Extra time the spoofer waits on top of the requested duration before exiting.
+pub const DEFAULT_GRACE_SECS: u32 = 15;
+/// Overrides [`DEFAULT_GRACE_SECS`] for both the launcher and the spoofers it starts.
+pub const GRACE_SECS_ENV: &str = "DOPPELGAMER_GRACE_SECS";
+
+/// Longest session that can be asked for. Anything longer is far more likely to be a typo than
+/// a real request.
+pub const MAX_DURATION_MINUTES: u32 = 24 * 60;
+
+/// Width and height of the spoofer window unless asked for something else, tall enough for
+/// everything the classic skin shows.
+pub const DEFAULT_WINDOW_SIZE: (u16, u16) = (320, 230);
+
+/// `strftime` format for the spoofer's exit time unless asked for something else.
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+/// How many seconds before the end the spoofer flashes on the taskbar, unless asked otherwise.
+pub const DEFAULT_FLASH_SECS: u32 = 60;
+
+/// How often the spoofer wakes up to check the time and update its window, in seconds.
+pub const DEFAULT_REFRESH_SECS: u32 = 1;
+
+/// Sets the log filter, using the usual `tracing` directive syntax like `debug` or
+/// `doppelgamer=trace`.
+pub const LOG_ENV: &str = "DOPPELGAMER_LOG";
+
+/// Reads the grace period from [`GRACE_SECS_ENV`], ignoring it if it isn't a valid number.
+#[must_use]
+pub fn grace_secs_from_env() -> Option<u32> {
+    std::env::var(GRACE_SECS_ENV).ok()?.trim().parse().ok()
+}
+
+/// Parses a session length such as `90`, `45m`, `2h` or `1h30m`. Bare numbers are minutes.
+///
+/// # Errors
+///
+/// Fails if the input isn't a duration jiff understands, isn't longer than zero, or is longer
+/// than [`MAX_DURATION_MINUTES`].
+pub fn parse_duration(input: &str) -> anyhow::Result<Span> {
+    let span = parse_uncapped_duration(input)?;
+    anyhow::ensure!(
+        duration_minutes(span)? <= f64::from(MAX_DURATION_MINUTES),
+        "Duration can be at most {} hours, got {}",
+        MAX_DURATION_MINUTES / 60,
+        input.trim()
+    );
+    Ok(span)
+}
+
+/// Like [`parse_duration`] with no upper limit, for callers that would rather cap it
+/// themselves than refuse it.
+///
+/// # Errors
+///
+/// Fails if the input isn't a duration jiff understands, isn't longer than zero, or uses
+/// calendar units like months that can't be turned into minutes.
+pub fn parse_uncapped_duration(input: &str) -> anyhow::Result<Span> {
+    let input = input.trim();
+    let span = match input.parse::<i64>() {
+        Ok(minutes) => Span::new().try_minutes(minutes)?,
+        Err(_) => input
+            .parse::<Span>()
+            .with_context(|| format!("Invalid duration: {input}"))?,
+    };
+    anyhow::ensure!(span.is_positive(), "Duration must be longer than zero");
+    duration_minutes(span)?;
+    Ok(span)
+}
+
+/// How many minutes a session length comes to, counting days as 24 hours.
+///
+/// # Errors
+///
+/// Fails if the span uses calendar units like months whose length depends on the date.
+pub fn duration_minutes(span: Span) -> anyhow::Result<f64> {
+    Ok(span.total((Unit::Minute, SpanRelativeTo::days_are_24_hours()))?)
+}
+
+/// Like [`parse_duration`], rounded to the nearest whole minute. Days count as 24 hours, the
+/// same as everywhere else a session length is measured.
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`parse_duration`].
+pub fn parse_minutes(input: &str) -> anyhow::Result<i64> {
+    let span = parse_duration(input)?.round(
+        SpanRound::new()
+            .largest(Unit::Minute)
+            .smallest(Unit::Minute)
+            .relative(SpanRelativeTo::days_are_24_hours()),
+    )?;
+    Ok(span.get_minutes())
+}
+
+/// Light or dark colours for the spoofer window, set with `--theme`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeChoice {
+    /// Follow the system's light or dark mode, and keep following it if it changes.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// How the spoofer window is laid out, set with `--skin`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SpooferSkin {
+    /// Everything: the exit time, time remaining and elapsed, and a progress bar.
+    #[default]
+    Classic,
+    /// Only the game, the time left and the buttons.
+    Minimal,
+    /// Looks like the game is loading, with the session as the progress bar.
+    Loading,
+}
+
+/// How the spoofer writes out time remaining and elapsed, set with `--countdown`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CountdownStyle {
+    /// Spelled out in units, like "1h 2m 3s".
+    #[default]
+    Words,
+    /// A clock, like "01:02:03". Hours keep counting past a day.
+    Clock,
+}
+
+/// A point in a session the spoofer calls out with a notification, set with `--alerts`.
+#[derive(Clone, Copy, Debug)]
+pub enum Alert {
+    /// Once this percentage of the session has gone by.
+    Elapsed(u8),
+    /// Once only this much of the session is left.
+    Remaining(Span),
+}
+
+impl std::fmt::Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Elapsed(percent) => write!(f, "{percent}%"),
+            Self::Remaining(span) => write!(f, "{span:#}"),
+        }
+    }
+}
+
+/// Parses an alert, either a percentage of the session like `50` or `50%`, or the time left
+/// like `1m`.
+///
+/// # Errors
+///
+/// Fails if the input is neither, the percentage isn't between 1 and 99, or the time left isn't
+/// longer than zero.
+pub fn parse_alert(input: &str) -> anyhow::Result<Alert> {
+    let input = input.trim();
+    if let Ok(percent) = input.strip_suffix('%').unwrap_or(input).parse::<u8>() {
+        anyhow::ensure!(
+            (1..100).contains(&percent),
+            "Alert percentages must be between 1 and 99, got {input}"
+        );
+        return Ok(Alert::Elapsed(percent));
+    }
+    let span: Span = input.parse().with_context(|| {
+        format!("Invalid alert {input}, expected a percentage like 50 or time left like 1m")
+    })?;
+    anyhow::ensure!(span.is_positive(), "Alert times must be longer than zero");
+    Ok(Alert::Remaining(span))
+}
+
+/// The alerts for a session, set with `--alerts` as a comma separated list, or `none` for no
+/// alerts at all.
+#[derive(Clone, Debug, Default)]
+pub struct Alerts(pub Vec<Alert>);
+
+impl std::fmt::Display for Alerts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("none");
+        }
+        let alerts: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&alerts.join(","))
+    }
+}
+
+/// Parses a comma separated list of alerts, each as [`parse_alert`] takes them, or `none`.
+///
+/// # Errors
+///
+/// Fails if any of the alerts is invalid.
+pub fn parse_alerts(input: &str) -> anyhow::Result<Alerts> {
+    if input.trim().eq_ignore_ascii_case("none") {
+        return Ok(Alerts::default());
+    }
+    input
+        .split(',')
+        .map(parse_alert)
+        .collect::<anyhow::Result<_>>()
+        .map(Alerts)
+}
+
+/// Parses a window size written as `WIDTHxHEIGHT`, such as `480x240`.
+///
+/// # Errors
+///
+/// Fails if the input isn't two whole numbers separated by an `x`, or either of them is zero.
+pub fn parse_window_size(input: &str) -> anyhow::Result<(u16, u16)> {
+    let input = input.trim();
+    let (width, height) = input
+        .split_once(['x', 'X'])
+        .with_context(|| format!("Window size should look like 480x240, got {input}"))?;
+    let width: u16 = width
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid window width: {width}"))?;
+    let height: u16 = height
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid window height: {height}"))?;
+    anyhow::ensure!(width > 0 && height > 0, "Window size must not be zero");
+    Ok((width, height))
+}
+
+/// Bounds for `--scale`. Past the top of it even the default window is bigger than most screens.
+pub const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
+/// Parses how much bigger to draw the spoofer window and everything in it, such as `1.5`.
+///
+/// # Errors
+///
+/// Fails if the input isn't a number within [`SCALE_RANGE`].
+pub fn parse_scale(input: &str) -> anyhow::Result<f32> {
+    let scale: f32 = input
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid scale: {input}"))?;
+    anyhow::ensure!(
+        SCALE_RANGE.contains(&scale),
+        "Scale must be between {} and {}, got {input}",
+        SCALE_RANGE.start(),
+        SCALE_RANGE.end()
+    );
+    Ok(scale)
+}
+
+/// Parses how many seconds the spoofer waits between updates. It has to divide a minute evenly
+/// so that the countdown can be shown in steps of it.
+///
+/// # Errors
+///
+/// Fails if the input isn't a whole number of seconds between 1 and 60 that divides 60.
+pub fn parse_refresh_secs(input: &str) -> anyhow::Result<u32> {
+    let secs: u32 = input
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid number of seconds: {input}"))?;
+    anyhow::ensure!(
+        (1..=60).contains(&secs) && 60 % secs == 0,
+        "Refresh interval must divide a minute evenly, like 5, 10 or 30 seconds"
+    );
+    Ok(secs)
+}
+
+/// Checks a `strftime` format for the spoofer's exit time, such as `%I:%M %p`, by trying it
+/// out on the current time.
+///
+/// # Errors
+///
+/// Fails if jiff can't format a time with it, for example because of an unknown directive.
+pub fn parse_time_format(input: &str) -> anyhow::Result<String> {
+    jiff::fmt::strtime::format(input, &jiff::Zoned::now())
+        .with_context(|| format!("Invalid time format: {input}"))?;
+    Ok(input.to_string())
+}
+
+/// The usual place for doppelgamer's config and data, unless [`set_dirs`] says otherwise.
+fn etc_strategy() -> anyhow::Result<impl etcetera::AppStrategy> {
+    let strategy = etcetera::choose_app_strategy(etcetera::AppStrategyArgs {
+        top_level_domain: "com".to_string(),
+        author: "backwardspy".to_string(),
+        app_name: "doppelgamer".to_string(),
+    })?;
+    Ok(strategy)
+}
+
+/// Keeps config somewhere else, such as next to doppelgamer on a USB stick. Spoofers are
+/// started with it set to whatever the launcher used.
+pub const CONFIG_DIR_ENV: &str = "DOPPELGAMER_CONFIG_DIR";
+/// Keeps data, spoofer copies and logs somewhere else. See [`CONFIG_DIR_ENV`].
+pub const DATA_DIR_ENV: &str = "DOPPELGAMER_DATA_DIR";
+
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Uses these directories instead of the usual ones for this platform. This has to happen before
+/// anything is read or written, logs included. Relative paths are taken from the current
+/// directory now, so they still mean the same place to the spoofers.
+///
+/// # Errors
+///
+/// Fails if either directory has already been chosen, or the current directory can't be found.
+pub fn set_dirs(config_dir: Option<PathBuf>, data_dir: Option<PathBuf>) -> anyhow::Result<()> {
+    if let Some(dir) = config_dir {
+        CONFIG_DIR
+            .set(std::path::absolute(dir)?)
+            .map_err(|_| anyhow::anyhow!("The config directory has already been chosen"))?;
+    }
+    if let Some(dir) = data_dir {
+        DATA_DIR
+            .set(std::path::absolute(dir)?)
+            .map_err(|_| anyhow::anyhow!("The data directory has already been chosen"))?;
+    }
+    Ok(())
+}
+
+/// `config` and `data` folders next to the running executable, for keeping everything on a USB
+/// stick instead of in the user's profile. They're created when first written to, like the
+/// usual ones.
+///
+/// # Errors
+///
+/// Fails if the path of the running executable can't be found.
+pub fn portable_dirs() -> anyhow::Result<(PathBuf, PathBuf)> {
+    let exe = std::env::current_exe().context("Failed to find the running executable")?;
+    let dir = exe
+        .parent()
+        .context("The running executable isn't in a directory")?;
+    Ok((dir.join("config"), dir.join("data")))
+}
+
+/// The directories chosen with [`set_dirs`], for passing on to spoofers.
+#[must_use]
+pub fn dir_overrides() -> [(&'static str, Option<&'static Path>); 2] {
+    [
+        (CONFIG_DIR_ENV, CONFIG_DIR.get().map(PathBuf::as_path)),
+        (DATA_DIR_ENV, DATA_DIR.get().map(PathBuf::as_path)),
+    ]
+}
+
+/// Where doppelgamer keeps its config, which may not exist yet.
+///
+/// # Errors
+///
+/// Fails if no directory was chosen and the home directory can't be found.
+pub fn config_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = CONFIG_DIR.get() {
+        return Ok(dir.clone());
+    }
+    Ok(etc_strategy()?.config_dir())
+}
+
+/// Where doppelgamer keeps its data, which may not exist yet.
+///
+/// # Errors
+///
+/// Fails if no directory was chosen and the home directory can't be found.
+pub fn data_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir.clone());
+    }
+    Ok(etc_strategy()?.data_dir())
+}
+
+/// Writes to a temporary file next to `path` and then swaps it into place, so getting killed
+/// halfway through never leaves a truncated file behind.
+///
+/// # Errors
+///
+/// Fails if `write` does, or the file can't be created or moved into place.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = File::create(&temp_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer)?;
+            writer.into_inner()?.sync_all()?;
+            Ok(fs::rename(&temp_path, path)?)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// A line in `history.jsonl`. The launcher records when a session starts and the spoofer
+/// records when it ends, and the two are matched up by pid.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HistoryEvent {
+    Started {
+        pid: u32,
+        name: String,
+        at: jiff::Timestamp,
+        minutes: u32,
+    },
+    Ended {
+        pid: u32,
+        at: jiff::Timestamp,
+    },
+}
+
+/// # Errors
+///
+/// Fails if the data directory can't be found or created.
+pub fn history_path() -> anyhow::Result<PathBuf> {
+    let mut path = data_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push("history.jsonl");
+    Ok(path)
+}
+
+/// Reads every event in the history file, oldest first. A missing file is an empty history,
+/// and lines that can't be read are skipped with a warning.
+///
+/// # Errors
+///
+/// Fails if the history file exists but can't be read.
+pub fn load_history() -> anyhow::Result<Vec<HistoryEvent>> {
+    let path = history_path()?;
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut events = Vec::new();
+    for (number, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(event) => events.push(event),
+            Err(e) => warn!("Skipping line {} of {}: {e}", number + 1, path.display()),
+        }
+    }
+    Ok(events)
+}
+
+/// Adds an event to the end of the history file.
+///
+/// # Errors
+///
+/// Fails if the history file can't be opened or written to.
+pub fn append_history(event: &HistoryEvent) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path()?)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Logs to stderr and to a daily rotating file in the data directory, deleting files older than
+/// a week. [`LOG_ENV`] replaces the default level if set, though `quiet` still keeps stderr down
+/// to errors. Levels on stderr are colored when it's a terminal and `NO_COLOR` isn't set.
+///
+/// # Errors
+///
+/// Fails if the data directory can't be found or created.
+pub fn init_logging(file_name: &str, quiet: bool, verbose: bool) -> anyhow::Result<()> {
+    let log_dir = data_dir()?;
+    fs::create_dir_all(&log_dir)?;
+    cleanup_old_logs(&log_dir, file_name);
+
+    let default_level = if verbose { Level::DEBUG } else { Level::INFO };
+    let filter = EnvFilter::builder()
+        .with_default_directive(default_level.into())
+        .with_env_var(LOG_ENV)
+        .from_env_lossy();
+    let stderr_level = if quiet { Level::ERROR } else { Level::TRACE };
+    let file_appender = tracing_appender::rolling::daily(&log_dir, file_name);
+    let color = std::io::stderr().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(color)
+                .with_writer(std::io::stderr.with_max_level(stderr_level)),
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(file_appender),
+        )
+        .init();
+    Ok(())
+}
+
+fn cleanup_old_logs(log_dir: &Path, file_name: &str) {
+    let Ok(entries) = fs::read_dir(log_dir) else {
+        return;
+    };
+    let prefix = format!("{file_name}.");
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !name.starts_with(&prefix) {
+            continue;
+        }
+        if let Ok(meta) = path.metadata()
+            && let Ok(modified) = meta.modified()
+            && let Ok(age) = modified.elapsed()
+            && age > std::time::Duration::from_secs(7 * 24 * 60 * 60)
+        {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(span: Span) -> f64 {
+        duration_minutes(span).expect("span should have no calendar units")
+    }
+
+    #[test]
+    fn bare_numbers_are_minutes() {
+        assert_eq!(parse_minutes("90").expect("should parse"), 90);
+        assert_eq!(parse_minutes(" 45 ").expect("should parse"), 45);
+    }
+
+    #[test]
+    fn spans_are_understood() {
+        assert_eq!(parse_minutes("1h30m").expect("should parse"), 90);
+        assert_eq!(parse_minutes("45m").expect("should parse"), 45);
+        assert_eq!(parse_minutes("2h").expect("should parse"), 120);
+        assert_eq!(parse_minutes("1d").expect("should parse"), 1440);
+    }
+
+    #[test]
+    fn durations_must_be_longer_than_zero() {
+        for input in ["0", "0m", "-5", "-1h"] {
+            assert!(parse_duration(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn nonsense_is_refused() {
+        for input in ["", "soon", "1x", "30-"] {
+            assert!(parse_duration(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn the_cap_is_inclusive() {
+        let max = MAX_DURATION_MINUTES;
+        assert!(parse_duration(&max.to_string()).is_ok());
+        assert!(parse_duration("24h").is_ok());
+        assert!(parse_duration(&(max + 1).to_string()).is_err());
+        assert!(parse_duration("24h1m").is_err());
+    }
+
+    #[test]
+    fn uncapped_durations_can_go_past_the_cap() {
+        let span =
+            parse_uncapped_duration(&(MAX_DURATION_MINUTES + 1).to_string()).expect("should parse");
+        assert!(minutes(span) > f64::from(MAX_DURATION_MINUTES));
+        assert!(parse_uncapped_duration("0").is_err());
+        assert!(parse_uncapped_duration("1mo").is_err());
+    }
+
+    #[test]
+    fn alerts_parse() {
+        let alerts = parse_alerts("50,1m").expect("should parse");
+        assert_eq!(alerts.to_string(), "50%,1m");
+        assert!(Alerts::default().0.is_empty());
+        assert!(parse_alerts("none").expect("should parse").0.is_empty());
+        assert_eq!(
+            parse_alerts("none").expect("should parse").to_string(),
+            "none"
+        );
+        for input in ["0", "100", "0m", "50,", "later"] {
+            assert!(parse_alerts(input).is_err(), "{input}");
+        }
+    }
+}
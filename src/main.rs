@@ -1,33 +1,393 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use std::fs::{self, File};
-use std::io::Write as _;
-use std::os::windows::process::CommandExt as _;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{IsTerminal as _, Write as _};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
 
-use etcetera::AppStrategy as _;
+use anyhow::Context as _;
+use clap::Parser as _;
+use doppelgamer::config::Config;
+use doppelgamer::games::{
+    self, DEFAULT_FETCH_RETRIES, GAMES_JSON_URL, GAMES_URL_ENV, SortOrder, exe_path_in,
+    exe_problem, fetch_remote_games, find_game, games_json_path, is_relative_exe, load_games,
+    sort_games, validate_games, windows_file_name_problem,
+};
+use doppelgamer::spoofers::{
+    self, SpooferOptions, find_running_spoofer, find_spoofer_copies, is_running,
+    load_tracked_spoofers, save_tracked_spoofers, stop_spoofer,
+};
+use doppelgamer::{Game, HistoryEvent, write_atomically};
 use iced::futures::SinkExt as _;
 use iced::futures::StreamExt as _;
 use iced::futures::channel::mpsc;
-use tracing::{error, info, warn};
-use tracing_subscriber::fmt::writer::MakeWriterExt as _;
+use owo_colors::{OwoColorize as _, Stream};
+use rand::Rng as _;
+use rand::seq::IndexedRandom as _;
+use tracing::{debug, error, info, warn};
 
 const ICON: &[u8] = include_bytes!("../resources/snek.ico");
-const BUNDLED_GAMES: &str = include_str!("../games.v2.json");
-const GAMES_JSON_URL: &str =
-    "https://raw.githubusercontent.com/backwardspy/doppelgamer/refs/heads/main/games.v2.json";
 const MAX_SHOWN_RESULTS: usize = 100;
+const DEFAULT_DURATION: u32 = 15;
+const DURATION_RANGE: RangeInclusive<u32> = 1..=doppelgamer::MAX_DURATION_MINUTES;
 
-#[cfg(debug_assertions)]
-const SPOOFER_BIN: &[u8] = include_bytes!("../target/debug/spoofer.exe");
-#[cfg(not(debug_assertions))]
-const SPOOFER_BIN: &[u8] = include_bytes!("../target/release/spoofer.exe");
+// build.rs finds the spoofer built for the same profile and target
+#[cfg(not(spoofer_bin_missing))]
+const SPOOFER_BIN: &[u8] = include_bytes!(env!("DOPPELGAMER_SPOOFER_BIN"));
+#[cfg(spoofer_bin_missing)]
+compile_error!(
+    "the spoofer binary hasn't been built yet, run `cargo build --bin spoofer` first, with \
+     --release for a release build"
+);
+#[cfg(spoofer_bin_missing)]
+const SPOOFER_BIN: &[u8] = &[];
 
+/// The most recent launch, offered again the next time the launcher opens.
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
-struct Game {
+struct LastLaunch {
     name: String,
-    exe: PathBuf,
+    duration: u32,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum Subcommand {
+    /// List running spoofers and how long they have left.
+    Status,
+    /// Close running spoofers early.
+    Stop {
+        /// Name of the game to stop.
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+        /// Stop every running spoofer.
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+    },
+    /// Add a game to the local games list.
+    AddGame {
+        /// Name shown in the launcher.
+        #[arg(long)]
+        name: String,
+        /// Directories the exe lives in, relative to the spoofer data directory.
+        #[arg(long, default_value = "")]
+        path: PathBuf,
+        /// File name of the game's exe.
+        #[arg(long, value_parser = parse_exe_name)]
+        exe: String,
+        /// Tag the game, for use with `--tag`. Repeat it to add several.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Another name to find the game by. Repeat it to add several.
+        #[arg(long = "alias")]
+        aliases: Vec<String>,
+        /// Image to use as the spoofer's window icon.
+        #[arg(long)]
+        icon: Option<PathBuf>,
+        /// Image for Discord to show with `--rpc`, as an asset key or image url.
+        #[arg(long, value_name = "KEY")]
+        rpc_image: Option<String>,
+        /// Replace an existing game with the same name.
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Remove a game from the local games list.
+    RemoveGame {
+        /// Name of the game to remove.
+        name: String,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Look for exes in a directory and offer to add each one to the local games list.
+    Scan {
+        /// Directory to search, such as the one your games are installed in.
+        dir: PathBuf,
+        /// Add everything found without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Show how long each game has been spoofed for.
+    Stats,
+    /// Delete spoofer copies that aren't currently running.
+    Clean,
+    /// Show where the games list, spoofer copies and logs live.
+    Paths {
+        /// Open the directories in the file explorer too.
+        #[arg(long)]
+        open: bool,
+    },
+    /// Check a games list for broken or duplicate entries.
+    Validate {
+        /// File to check instead of the local games list.
+        path: Option<PathBuf>,
+    },
+    /// Open the local games list in a text editor, and check it once the editor closes.
+    Edit,
+    /// Swap the games list with the copy saved before the last download.
+    Restore,
+    /// Write the settings, games list and profiles to one file, for moving to another machine.
+    Export {
+        /// File to write.
+        path: PathBuf,
+    },
+    /// Bring back settings, games list and profiles from a file made with `export`.
+    Import {
+        /// File made with `export`.
+        path: PathBuf,
+        /// Overwrite existing files without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Manage separate games lists, chosen with `--profile`.
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum ProfileCommand {
+    /// List the profiles that have a games list.
+    List,
+}
+
+/// Game names and minutes from `--playlist`, in the order they're played.
+#[derive(Clone, Debug)]
+struct Playlist(Vec<(String, u32)>);
+
+/// Makes Discord think you're playing a game.
+#[derive(Clone, Debug, clap::Parser)]
+#[command(version = env!("DOPPELGAMER_VERSION"))]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+    /// Game to spoof, by name or by its number in `--list`. Shorthand for `--game`.
+    #[arg(value_name = "GAME", conflicts_with_all = ["game", "random", "playlist", "list"])]
+    game_name: Option<String>,
+    /// Session length, like `--minutes`. With a game as well it launches without asking.
+    #[arg(
+        value_name = "MINUTES",
+        requires = "game_name",
+        conflicts_with_all = ["minutes", "until"]
+    )]
+    duration: Option<String>,
+    /// Never touch the network, using the local games list as it is.
+    #[arg(long, visible_alias = "offline")]
+    local: bool,
+    /// Fetch the games list from here instead. Repeat it to list mirrors, tried in order until
+    /// one works.
+    #[arg(long, env = GAMES_URL_ENV, value_parser = parse_games_url)]
+    games_url: Vec<reqwest::Url>,
+    /// How many times to retry fetching the games list if the connection fails.
+    #[arg(long, default_value_t = DEFAULT_FETCH_RETRIES)]
+    retries: u32,
+    /// Replace an already running spoofer for the same game without asking, which is the only
+    /// way to replace one without a console to ask on.
+    #[arg(long)]
+    force: bool,
+    #[command(flatten)]
+    spoofer: SpooferOptions,
+    /// Print the names of all known games and exit.
+    #[arg(long)]
+    list: bool,
+    /// Use the games list of this profile instead of the default one.
+    #[arg(long, global = true, value_parser = games::parse_profile_name)]
+    profile: Option<String>,
+    /// Merge the downloaded games list into the `--profile` games list too, which is otherwise
+    /// left as it is.
+    #[arg(long, requires = "profile")]
+    sync_profile: bool,
+    /// Keep config and games lists here instead of the usual place.
+    #[arg(long, global = true, env = doppelgamer::CONFIG_DIR_ENV, value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+    /// Keep data, spoofer copies and logs here instead of the usual place.
+    #[arg(long, global = true, env = doppelgamer::DATA_DIR_ENV, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    /// Keep everything in config and data folders next to doppelgamer instead of the usual
+    /// place, for running it from a USB stick.
+    #[arg(long, global = true, conflicts_with_all = ["config_dir", "data_dir"])]
+    portable: bool,
+    /// Only list and offer games with this tag.
+    #[arg(long)]
+    tag: Option<String>,
+    /// How to order the games list.
+    #[arg(long, value_enum, default_value_t)]
+    sort: SortOrder,
+    /// Print full games list entries as json.
+    #[arg(long, requires = "list")]
+    json: bool,
+    /// Preselect a game by name. Repeat it to spoof several games at once.
+    #[arg(long)]
+    game: Vec<String>,
+    /// Only print errors and the final result.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Print extra detail, like file paths and how long downloads take.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Pick a game at random.
+    #[arg(long, conflicts_with = "game")]
+    random: bool,
+    /// Spoof several games one after another, like "Game A:30,Game B:45". Durations work like
+    /// `--minutes`.
+    #[arg(long, value_parser = parse_playlist, conflicts_with_all = ["game", "random", "minutes"])]
+    playlist: Option<Playlist>,
+    /// Session length, like 30, 45m or 1h. A range like 30-90 picks one at random.
+    #[arg(long, value_parser = parse_minutes_arg)]
+    minutes: Option<u32>,
+    /// Spoof until a time of day, like 18:00, or a date and time, like 2025-01-01T18:00,
+    /// instead of for a number of minutes.
+    #[arg(
+        long,
+        value_name = "TIME",
+        value_parser = parse_until,
+        conflicts_with_all = ["minutes", "playlist"]
+    )]
+    until: Option<u32>,
+    /// Print where the spoofer would go and how it would be started, without launching it.
+    #[arg(long)]
+    dry_run: bool,
+    /// Check that each game's spoofer copy could be written, and point out the ones that
+    /// couldn't. Always on with `--verbose`.
+    #[arg(long)]
+    validate: bool,
+    /// Launch straight away with `--game` and `--minutes` instead of asking first.
+    #[arg(short, long, visible_alias = "no-confirm")]
+    yes: bool,
+    /// What the launcher suggests when `--minutes` isn't given.
+    #[arg(skip = DEFAULT_DURATION)]
+    default_minutes: u32,
+}
+
+impl Args {
+    /// Fills in whatever wasn't given on the command line or in the environment.
+    fn apply_config(&mut self, config: &Config) {
+        if self.games_url.is_empty() {
+            let primary = config.games_url.as_deref().unwrap_or(GAMES_JSON_URL);
+            let mirrors = config.mirrors.iter().flatten().map(String::as_str);
+            for url in std::iter::once(primary).chain(mirrors) {
+                match parse_games_url(url) {
+                    Ok(url) => self.games_url.push(url),
+                    Err(e) => warn!("Ignoring games list url from config: {e:#}"),
+                }
+            }
+        }
+        if self.spoofer.grace_secs.is_none() && doppelgamer::grace_secs_from_env().is_none() {
+            self.spoofer.grace_secs = config.grace_secs;
+        }
+        self.spoofer.tray |= config.tray.unwrap_or(false);
+        self.spoofer.always_on_top |= config.always_on_top.unwrap_or(false);
+        self.spoofer.keep_awake |= config.keep_awake.unwrap_or(false);
+        self.spoofer.notify |= config.notify.unwrap_or(false);
+        if self.spoofer.time_format.is_none()
+            && let Some(time_format) = &config.time_format
+        {
+            match doppelgamer::parse_time_format(time_format) {
+                Ok(time_format) => self.spoofer.time_format = Some(time_format),
+                Err(e) => warn!("Ignoring time_format from config: {e:#}"),
+            }
+        }
+        if let Some(minutes) = config.default_minutes {
+            match validate_duration(minutes) {
+                Ok(minutes) => self.default_minutes = minutes,
+                Err(e) => warn!("Ignoring default_minutes from config: {e:#}"),
+            }
+        }
+    }
+
+    /// Whether to download the games list. `--local` never does, and neither does a profile
+    /// without `--sync-profile`, since its list is meant to be its own.
+    const fn fetches(&self) -> bool {
+        !self.local && (self.profile.is_none() || self.sync_profile)
+    }
+
+    fn games_urls(&self) -> Vec<reqwest::Url> {
+        if self.games_url.is_empty() {
+            vec![reqwest::Url::parse(GAMES_JSON_URL).expect("default games url should be valid")]
+        } else {
+            self.games_url.clone()
+        }
+    }
+}
+
+/// Accepts a single duration, or a range like `30-90` to pick a random one from.
+fn parse_minutes_arg(value: &str) -> anyhow::Result<u32> {
+    let Some((low, high)) = value.split_once('-') else {
+        return parse_single_minutes(value);
+    };
+    let (low, high) = (parse_single_minutes(low)?, parse_single_minutes(high)?);
+    anyhow::ensure!(
+        low <= high,
+        "Duration range {value} must go from shortest to longest"
+    );
+    Ok(rand::rng().random_range(low..=high))
+}
+
+/// Splits a playlist like `Game A:30,Game B:45` into games and durations. The duration comes
+/// after the last colon, so game names can have colons of their own.
+fn parse_playlist(value: &str) -> anyhow::Result<Playlist> {
+    value
+        .split(',')
+        .map(|entry| {
+            let (name, minutes) = entry
+                .rsplit_once(':')
+                .with_context(|| format!("Playlist entry {entry:?} should look like Game:30"))?;
+            let name = name.trim();
+            anyhow::ensure!(!name.is_empty(), "Playlist entry {entry:?} has no game");
+            Ok((name.to_string(), parse_minutes_arg(minutes)?))
+        })
+        .collect::<anyhow::Result<_>>()
+        .map(Playlist)
+}
+
+/// Minutes from now until `value`, rounded up so the session lasts until at least then. A bare
+/// time of day means today.
+fn parse_until(value: &str) -> anyhow::Result<u32> {
+    let value = value.trim();
+    let now = jiff::Zoned::now();
+    let end = if let Ok(time) = value.parse::<jiff::civil::Time>() {
+        now.with().time(time).build()?
+    } else {
+        value
+            .parse::<jiff::civil::DateTime>()
+            .with_context(|| {
+                format!("Invalid end time {value:?}, it should look like 18:00 or 2025-01-01T18:00")
+            })?
+            .to_zoned(now.time_zone().clone())?
+    };
+    anyhow::ensure!(end > now, "{value} has already passed");
+    let seconds = end.timestamp().as_second() - now.timestamp().as_second();
+    let minutes = u32::try_from((seconds + 59) / 60).context("End time is too far away")?;
+    validate_duration(minutes)
+}
+
+fn parse_single_minutes(value: &str) -> anyhow::Result<u32> {
+    let minutes = doppelgamer::parse_minutes(value)?;
+    let minutes = u32::try_from(minutes).context("Duration is too long")?;
+    validate_duration(minutes)
+}
+
+/// The exe name must be a plain file name, directories belong in `--path`.
+fn parse_exe_name(exe_name: &str) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        !exe_name.is_empty() && exe_name != "." && exe_name != "..",
+        "Invalid exe name: {exe_name:?}"
+    );
+    anyhow::ensure!(
+        !exe_name.contains(['/', '\\']),
+        "Exe name {exe_name:?} must not contain path separators, use --path for directories"
+    );
+    if cfg!(windows)
+        && let Some(problem) = windows_file_name_problem(Path::new(exe_name))
+    {
+        anyhow::bail!("Invalid exe name: {problem}");
+    }
+    Ok(exe_name.to_string())
+}
+
+fn parse_games_url(value: &str) -> anyhow::Result<reqwest::Url> {
+    reqwest::Url::parse(value).with_context(|| format!("Invalid games url: {value}"))
 }
 
 #[derive(Clone, Debug)]
@@ -36,13 +396,39 @@ enum MatcherCommand {
     ReloadGames(Vec<Game>),
 }
 
+/// What the Refresh button says.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RefreshState {
+    Idle,
+    Refreshing,
+    /// The last refresh didn't get anywhere, until the next one is started.
+    Failed,
+}
+
 struct App {
     query: String,
     games: Vec<Game>,
-    selected_game: Option<Game>,
+    selected_games: Vec<Game>,
+    highlighted: Option<usize>,
     duration: u32,
+    pending_replace: Option<(Vec<Game>, u32)>,
+    last_launch: Option<(Game, u32)>,
+    force: bool,
+    dry_run: bool,
+    tag: Option<String>,
+    sort: SortOrder,
+    validate: bool,
+    /// What's wrong with each game that can't be spoofed, by name. Only filled in with
+    /// `--validate`.
+    problems: HashMap<String, String>,
+    spoofer_options: SpooferOptions,
     matcher_tx: Option<mpsc::Sender<MatcherCommand>>,
-    initial_games: Option<Vec<Game>>,
+    all_games: Vec<Game>,
+    /// Where the Refresh button fetches from. Empty when not fetching at all, as with `--local`,
+    /// in which case it reads the local games list again instead.
+    games_urls: Vec<reqwest::Url>,
+    retries: u32,
+    refresh: RefreshState,
     mode: iced::theme::Mode,
 }
 
@@ -51,241 +437,1222 @@ enum Message {
     MatcherReady(mpsc::Sender<MatcherCommand>),
     SystemThemeChanged(iced::theme::Mode),
     GamesUpdated(Option<Vec<Game>>),
+    Refresh,
+    Refreshed(Option<Vec<Game>>),
     Suggest(Vec<Game>),
     Search(String),
+    SubmitSearch,
+    HighlightNext,
+    HighlightPrevious,
+    Random,
     Select(Game),
     SetDuration(u32),
-    Launch(Game, u32),
+    SetTray(bool),
+    SetAlwaysOnTop(bool),
+    Launch(Vec<Game>, u32),
+    LaunchChecked(Vec<(Game, bool)>, u32),
+    Replace,
+    CancelReplace,
 }
 
-fn games_json_path() -> anyhow::Result<PathBuf> {
-    let mut path = etc_strategy()?.config_dir();
-    fs::create_dir_all(&path)?;
-    path.push("games.v2.json");
-    Ok(path)
+/// Attached to errors that scripts may want to tell apart, picking the exit code listed in the
+/// README. Anything else exits with 1, and clap exits with 2 for arguments it can't make sense
+/// of, the same as [`Failure::InvalidSelection`].
+#[derive(Clone, Copy, Debug)]
+enum Failure {
+    InvalidSelection,
+    SaveGames,
+    /// Anything else that couldn't be written, like a spoofer copy or imported settings.
+    Write,
 }
 
-fn ensure_local_games_json() {
-    info!("Ensuring local games.json exists");
-    let Ok(path) = games_json_path() else {
-        warn!("Failed to determine games.json path, skipping local cache");
-        return;
-    };
+impl Failure {
+    const fn exit_code(self) -> u8 {
+        match self {
+            Self::InvalidSelection => 2,
+            Self::SaveGames | Self::Write => 4,
+        }
+    }
+}
 
-    if !path.exists()
-        && let Ok(mut file) = File::create(&path)
-    {
-        let _ = file.write_all(BUNDLED_GAMES.as_bytes());
-        info!("Wrote bundled games.json to local cache");
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::InvalidSelection => "Invalid selection",
+            Self::SaveGames => "Failed to save the games list",
+            Self::Write => "Failed to write to disk",
+        })
     }
 }
 
-fn load_games() -> Vec<Game> {
-    info!("Loading games from local cache");
-    ensure_local_games_json();
-    if let Some(path) = games_json_path().ok()
-        && let Ok(data) = fs::read_to_string(&path)
-        && let Ok(games) = serde_json::from_str(&data)
-    {
-        return games;
+fn validate_duration(minutes: u32) -> anyhow::Result<u32> {
+    if DURATION_RANGE.contains(&minutes) {
+        Ok(minutes)
+    } else {
+        anyhow::bail!(
+            "Duration must be between {} and {} minutes, got {minutes}",
+            DURATION_RANGE.start(),
+            DURATION_RANGE.end()
+        )
     }
-    serde_json::from_str(BUNDLED_GAMES).expect("bundled games json should be valid")
 }
 
-async fn fetch_remote_games() -> Option<Vec<Game>> {
-    info!("Fetching games from remote");
-    let response = match reqwest::get(GAMES_JSON_URL).await {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to fetch remote games.json: {e}");
-            return None;
-        }
+fn validate_games_file(path: Option<&Path>) -> anyhow::Result<()> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => games_json_path()?,
     };
-    if !response.status().is_success() {
-        warn!(
-            "Failed to fetch remote games.json, status: {}",
-            response.status()
+    let data =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let report = validate_games(&data);
+
+    for error in &report.errors {
+        println!(
+            "{} {error}",
+            "error:".if_supports_color(Stream::Stdout, |text| text.red())
+        );
+    }
+    for warning in &report.warnings {
+        println!(
+            "{} {warning}",
+            "warning:".if_supports_color(Stream::Stdout, |text| text.yellow())
         );
-        return None;
     }
+    println!(
+        "{}: {} entries, {} errors, {} warnings",
+        path.display(),
+        report.entries,
+        report.errors.len(),
+        report.warnings.len()
+    );
+    anyhow::ensure!(report.errors.is_empty(), "{} is not valid", path.display());
+    Ok(())
+}
 
-    info!("Remote games.json fetched, parsing response");
-    let games: Vec<Game> = match response.json().await {
-        Ok(g) => g,
-        Err(e) => {
-            warn!("Failed to parse remote games.json: {e}");
-            return None;
+fn add_game(game: Game, overwrite: bool) -> anyhow::Result<()> {
+    if !is_relative_exe(&game.exe) {
+        anyhow::bail!(
+            "{} must be a relative path inside the data directory",
+            game.exe.display()
+        );
+    }
+    let locked = games::lock_games().context(Failure::SaveGames)?;
+    let mut games = locked.load();
+    match games.iter_mut().find(|existing| existing.name == game.name) {
+        Some(_) if !overwrite => anyhow::bail!(
+            "{:?} is already in the games list, use --overwrite to replace it",
+            game.name
+        ),
+        Some(existing) => *existing = game.clone(),
+        None => games.push(game.clone()),
+    }
+    locked.save(&games).context(Failure::SaveGames)?;
+    println!("Added {} ({})", game.name, game.exe.display());
+    Ok(())
+}
+
+/// Every exe under `dir`, relative to it and in a stable order. Symlinks aren't followed, so a
+/// link back up the tree can't send this round in circles.
+fn find_exes(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut exes = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            // only the top level has to be readable, anything below is skipped
+            Err(e) if current != dir => {
+                warn!("Skipping {}: {e}", current.display());
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+                && let Ok(relative) = path.strip_prefix(dir)
+            {
+                exes.push(relative.to_path_buf());
+            }
+        }
+    }
+    exes.sort();
+    Ok(exes)
+}
+
+/// Names a found exe after the folder it was installed into, falling back on the exe's own
+/// name when it sits right in the scanned directory.
+fn guess_game_name(exe: &Path) -> String {
+    let mut components = exe.components();
+    let first = components.next().map(|c| c.as_os_str().to_string_lossy());
+    match (first, components.next()) {
+        (Some(folder), Some(_)) => folder.into_owned(),
+        _ => exe
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+    }
+}
+
+fn scan_games(dir: &Path, yes: bool) -> anyhow::Result<()> {
+    let found = find_exes(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    let games = load_games();
+    let mut found_games = Vec::new();
+    for exe in found {
+        // uninstallers are in nearly every game's folder and never what anyone wants
+        if exe
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().to_lowercase().starts_with("unins"))
+        {
+            continue;
+        }
+        let exe_lower = exe.to_string_lossy().to_lowercase();
+        if games
+            .iter()
+            .any(|game| game.exe.to_string_lossy().to_lowercase() == exe_lower)
+        {
+            continue;
+        }
+        if let Some(problem) = windows_file_name_problem(&exe) {
+            warn!("Skipping {}: {problem}", exe.display());
+            continue;
+        }
+        let name = guess_game_name(&exe);
+        if games
+            .iter()
+            .chain(&found_games)
+            .any(|game| game.name == name)
+        {
+            info!(
+                "Skipping {}, there's already a game called {name:?}",
+                exe.display()
+            );
+            continue;
+        }
+        if !yes && !confirm(&format!("Add {name} ({})?", exe.display()))? {
+            continue;
+        }
+        found_games.push(Game {
+            name,
+            exe,
+            tags: Vec::new(),
+            aliases: Vec::new(),
+            icon: None,
+            rpc_image: None,
+            local: true,
+        });
+    }
+    // asking can take a while, so the list is only locked once it's known what to add, and read
+    // again in case it changed in the meantime
+    let mut added = 0;
+    if !found_games.is_empty() {
+        let locked = games::lock_games().context(Failure::SaveGames)?;
+        let mut games = locked.load();
+        for game in found_games {
+            if !games.iter().any(|existing| existing.name == game.name) {
+                games.push(game);
+                added += 1;
+            }
         }
+        locked.save(&games).context(Failure::SaveGames)?;
+    }
+    println!("Added {added} games");
+    Ok(())
+}
+
+fn remove_game(name: &str, yes: bool) -> anyhow::Result<()> {
+    // an exact match only, so that a loose one can't remove the wrong game without asking
+    let game = games::find_game_exactly(&load_games(), name).context(Failure::InvalidSelection)?;
+    if !yes && !confirm(&format!("Remove {} ({})?", game.name, game.exe.display()))? {
+        println!("Cancelled");
+        return Ok(());
+    }
+    let locked = games::lock_games().context(Failure::SaveGames)?;
+    let mut games = locked.load();
+    games.retain(|existing| existing.name != game.name);
+    locked.save(&games).context(Failure::SaveGames)?;
+    println!("Removed {}", game.name);
+    Ok(())
+}
+
+/// Whether there's anyone at a console to answer [`confirm`]. There isn't when started from a
+/// shortcut or hotkey, or with input piped in.
+fn can_ask() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Asks a yes or no question on the console.
+///
+/// # Errors
+///
+/// Fails without a console to ask on, since reading no answer as a quiet no would leave
+/// nothing to say why nothing happened.
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    anyhow::ensure!(
+        can_ask(),
+        "There's no console to ask {prompt:?} on, pass --yes to go ahead without asking"
+    );
+    print!("{prompt} [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    Ok(std::io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Whether to replace a spoofer already running for `game`, or `None` to leave it be and not
+/// launch. `--force` replaces it without asking, and otherwise it's asked about on the console.
+/// With nobody to ask the launch goes ahead without replacing, which turns it away with a hint
+/// about `--force`.
+fn ask_to_replace(game: &Game, force: bool) -> anyhow::Result<Option<bool>> {
+    if force {
+        return Ok(Some(true));
+    }
+    let running = exe_path_in(&doppelgamer::data_dir()?, game)
+        .ok()
+        .and_then(|path| find_running_spoofer(&path));
+    let Some(running) = running.filter(|_| can_ask()) else {
+        return Ok(Some(false));
     };
-    info!("Parsed {} games from remote", games.len());
+    let prompt = format!(
+        "{} is already running (pid {}), replace it?",
+        game.name, running.pid
+    );
+    Ok(confirm(&prompt)?.then_some(true))
+}
+
+fn launch_prompt(games: &[Game], minutes: u32, options: &SpooferOptions) -> String {
+    let quit_time = jiff::Zoned::now().saturating_add(
+        jiff::Span::new()
+            .minutes(minutes)
+            .seconds(options.grace_secs()),
+    );
+    format!(
+        "Launch {} for {minutes} minutes, exiting at {}?",
+        game_names(games).if_supports_color(Stream::Stdout, |text| text.bold()),
+        quit_time.strftime(
+            options
+                .time_format
+                .as_deref()
+                .unwrap_or(doppelgamer::DEFAULT_TIME_FORMAT)
+        )
+    )
+}
+
+fn print_dry_run(game: &Game, minutes: u32, options: &SpooferOptions) -> anyhow::Result<()> {
+    // a dry run leaves the data directory alone, so this is only where the copy would go
+    let exe_path = exe_path_in(&doppelgamer::data_dir()?, game)?;
+    let quit_time = options
+        .quit_time(minutes)?
+        .to_zoned(jiff::tz::TimeZone::system());
+    let time_format = options
+        .time_format
+        .as_deref()
+        .unwrap_or(doppelgamer::DEFAULT_TIME_FORMAT);
+    println!(
+        "Would launch {} for {minutes} minutes",
+        game.name
+            .if_supports_color(Stream::Stdout, |text| text.bold())
+    );
+    println!("  exe: {}", exe_path.display());
+    println!("  exits at: {}", quit_time.strftime(time_format));
+    println!(
+        "  command: {:?}",
+        spoofers::spoofer_command(&exe_path, game, minutes, options)
+    );
+    Ok(())
+}
+
+/// What's wrong with each game whose spoofer copy couldn't be written, by name.
+fn exe_problems(games: &[Game]) -> HashMap<String, String> {
+    games
+        .iter()
+        .filter_map(|game| Some((game.name.clone(), exe_problem(game)?)))
+        .collect()
+}
+
+/// Narrows the games down to those with `tag`, if there is one, and puts them in order. Games
+/// sharing an exe are told apart first, so that which one is first doesn't depend on the tag.
+fn arrange_games(games: &mut Vec<Game>, tag: Option<&str>, order: SortOrder) {
+    games::separate_shared_exes(games);
+    if let Some(tag) = tag {
+        games.retain(|game| game.has_tag(tag));
+    }
+    sort_games(games, order);
+}
 
-    if let Ok(path) = games_json_path()
-        && let Ok(mut file) = File::create(&path)
-        && let Err(e) = serde_json::to_writer(&mut file, &games)
+/// Finds a game given without `--game`, which can also be its number in `--list`, counting from
+/// one. A game that's actually named that number comes first.
+fn find_listed_game(games: &[Game], name: &str) -> anyhow::Result<Game> {
+    if let Ok(number) = name.parse::<usize>()
+        && !games.iter().any(|game| game.names().any(|own| own == name))
     {
-        error!("Failed to write fetched games to local cache: {e}");
+        return number
+            .checked_sub(1)
+            .and_then(|index| games.get(index))
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "There's no game number {number}, the list has {}",
+                    games.len()
+                )
+            });
     }
+    find_game(games, name)
+}
 
-    Some(games)
+fn print_games(games: &[Game], json: bool) -> anyhow::Result<()> {
+    let mut out = std::io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut out, games)?;
+        writeln!(out)?;
+    } else {
+        for game in games {
+            writeln!(out, "{}", game.name)?;
+        }
+    }
+    Ok(())
 }
 
-fn etc_strategy() -> anyhow::Result<impl etcetera::AppStrategy> {
-    let strategy = etcetera::choose_app_strategy(etcetera::AppStrategyArgs {
-        top_level_domain: "com".to_string(),
-        author: "backwardspy".to_string(),
-        app_name: "doppelgamer".to_string(),
-    })?;
-    Ok(strategy)
+fn restore_games() -> anyhow::Result<()> {
+    games::restore_games_backup().context(Failure::SaveGames)?;
+    println!("Restored the games list from before the last download");
+    Ok(())
 }
 
-fn game_exe_path(game: &Game) -> anyhow::Result<PathBuf> {
-    let mut path = etc_strategy()?.data_dir();
-    path.push(&game.exe);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+fn print_profiles() -> anyhow::Result<()> {
+    let current = games::profile();
+    let marker = |active: bool| if active { "* " } else { "  " };
+    println!("{}default", marker(current.is_none()));
+    for profile in games::list_profiles()? {
+        println!("{}{profile}", marker(current == Some(profile.as_str())));
     }
-    info!("Using spoofer path: {}", path.display());
-    Ok(path)
+    Ok(())
 }
 
-fn launch_spoofer(game: Game, duration: u32) {
-    info!(
-        "Launching spoofer for {} with duration {} minutes",
-        game.name, duration
+/// Bumped whenever the layout of [`Bundle`] changes in a way older versions can't read.
+const BUNDLE_VERSION: u32 = 1;
+
+/// What `export` writes and `import` reads: the contents of each file it covers, by its name in
+/// the config directory. Plain json keeps it readable and needs nothing extra to unpack.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Bundle {
+    version: u32,
+    files: BTreeMap<String, String>,
+}
+
+fn export_setup(path: &Path) -> anyhow::Result<()> {
+    let config_dir = doppelgamer::config_dir()?;
+    let mut names = vec!["config.toml".to_string(), "games.v2.json".to_string()];
+    names.extend(
+        games::list_profiles()?
+            .into_iter()
+            .map(|profile| format!("games.{profile}.v2.json")),
+    );
+    let mut files = BTreeMap::new();
+    for name in names {
+        match fs::read_to_string(config_dir.join(&name)) {
+            Ok(data) => {
+                files.insert(name, data);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {name}")),
+        }
+    }
+    let count = files.len();
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        files,
+    };
+    write_atomically(path, |file| {
+        Ok(serde_json::to_writer_pretty(file, &bundle)?)
+    })
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Exported {count} files to {}", path.display());
+    Ok(())
+}
+
+/// Checks everything in the file before writing any of it, so that a broken or foreign file
+/// can't leave a working setup half replaced.
+fn import_setup(path: &Path, yes: bool) -> anyhow::Result<()> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let bundle: Bundle = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a doppelgamer export", path.display()))?;
+    anyhow::ensure!(
+        bundle.version <= BUNDLE_VERSION,
+        "{} was exported by a newer doppelgamer",
+        path.display()
     );
+    for (name, contents) in &bundle.files {
+        check_bundled_file(name, contents)?;
+    }
+
+    let config_dir = doppelgamer::config_dir()?;
+    let existing: Vec<&str> = bundle
+        .files
+        .keys()
+        .filter(|name| config_dir.join(name).exists())
+        .map(String::as_str)
+        .collect();
+    if !existing.is_empty() && !yes && !confirm(&format!("Overwrite {}?", existing.join(", ")))? {
+        println!("Imported nothing");
+        return Ok(());
+    }
+
+    fs::create_dir_all(&config_dir).context(Failure::Write)?;
+    for (name, contents) in &bundle.files {
+        write_atomically(&config_dir.join(name), |file| {
+            Ok(file.write_all(contents.as_bytes())?)
+        })
+        .with_context(|| format!("Failed to write {name}"))
+        .context(Failure::Write)?;
+    }
+    println!(
+        "Imported {} files into {}",
+        bundle.files.len(),
+        config_dir.display()
+    );
+    Ok(())
+}
+
+/// Only the settings and games lists are let in, each of which has to be valid, and names
+/// can't point anywhere outside the config directory.
+fn check_bundled_file(name: &str, contents: &str) -> anyhow::Result<()> {
+    if name == "config.toml" {
+        toml::from_str::<Config>(contents).context("config.toml in the export is invalid")?;
+        return Ok(());
+    }
+    let is_games_list = name == "games.v2.json"
+        || name
+            .strip_prefix("games.")
+            .and_then(|rest| rest.strip_suffix(".v2.json"))
+            .is_some_and(|profile| games::parse_profile_name(profile).is_ok());
+    anyhow::ensure!(is_games_list, "Unexpected file in the export: {name:?}");
+    let report = validate_games(contents);
+    anyhow::ensure!(
+        report.errors.is_empty(),
+        "{name} in the export is not valid: {}",
+        report.errors.join(", ")
+    );
+    Ok(())
+}
+
+fn print_paths(open: bool) -> anyhow::Result<()> {
+    let config_dir = doppelgamer::config_dir()?;
+    let data_dir = doppelgamer::data_dir()?;
+    println!("config: {}", config_dir.display());
+    println!(
+        "settings: {}",
+        doppelgamer::config::config_path()?.display()
+    );
+    println!("games list: {}", games_json_path()?.display());
+    println!("data: {}", data_dir.display());
+    if open {
+        open_in_explorer(&config_dir)?;
+        if data_dir != config_dir {
+            open_in_explorer(&data_dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn open_in_explorer(dir: &Path) -> anyhow::Result<()> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    fs::create_dir_all(dir)?;
+    // explorer exits with a failure code even when it worked, so only spawning is checked
+    let _child = Command::new(program)
+        .arg(dir)
+        .spawn()
+        .with_context(|| format!("Failed to open {}", dir.display()))?;
+    Ok(())
+}
+
+/// Opens `path` in `$VISUAL` or `$EDITOR`, falling back on notepad or vi, and waits for the
+/// editor to close. Editors that need a flag to wait, like `code --wait`, can have it in the
+/// variable.
+fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .ok()
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("The editor command is empty")?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start {editor}"))?;
+    anyhow::ensure!(status.success(), "{editor} exited with {status}");
+    Ok(())
+}
+
+/// Lets the games list be edited by hand, checking it after every edit and offering to go
+/// back in if it's broken, since a broken list is swapped for the built in one on next load.
+fn edit_games() -> anyhow::Result<()> {
+    // makes sure there is a file to open
+    let _ = load_games();
+    let path = games_json_path()?;
+    loop {
+        open_in_editor(&path)?;
+        match validate_games_file(Some(&path)) {
+            Ok(()) => return Ok(()),
+            // without a console to ask on, the problems are reported as an error instead
+            Err(e) if !confirm("Open it again to fix it?").unwrap_or(false) => return Err(e),
+            Err(_) => {}
+        }
+    }
+}
+
+fn last_launch_path() -> anyhow::Result<PathBuf> {
+    let mut path = doppelgamer::config_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push("last.json");
+    Ok(path)
+}
+
+fn load_last_launch() -> Option<LastLaunch> {
+    let data = fs::read_to_string(last_launch_path().ok()?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn save_last_launch(last: &LastLaunch) -> anyhow::Result<()> {
+    write_atomically(&last_launch_path()?, |file| {
+        Ok(serde_json::to_writer(file, last)?)
+    })
+}
+
+fn print_status() -> anyhow::Result<()> {
+    // a spoofer that answers knows its own time left, which is the one to trust after pausing
+    // or extending it
+    let spoofers: Vec<_> = load_tracked_spoofers()
+        .into_iter()
+        .filter_map(|mut spoofer| match spoofers::query_status(spoofer.pid) {
+            Ok(left) => {
+                if let Ok(quit_time) =
+                    jiff::Timestamp::now().checked_add(jiff::Span::new().seconds(left))
+                {
+                    spoofer.quit_time = quit_time;
+                }
+                Some(spoofer)
+            }
+            Err(e) => {
+                debug!(
+                    "No status from {} (pid {}): {e:#}",
+                    spoofer.name, spoofer.pid
+                );
+                is_running(&spoofer).then_some(spoofer)
+            }
+        })
+        .collect();
+    save_tracked_spoofers(&spoofers)?;
+
+    if spoofers.is_empty() {
+        println!("No running spoofers");
+    }
+    for spoofer in &spoofers {
+        println!(
+            "{} (pid {}): {}",
+            spoofer.name,
+            spoofer.pid,
+            format_remaining(spoofer.quit_time)
+        );
+    }
+    Ok(())
+}
 
+/// Sums up the history file into total spoofed time, overall and per game.
+fn print_stats() -> anyhow::Result<()> {
+    // sessions as (name, start, planned end, actual end)
+    let mut sessions = Vec::new();
+    let mut open: HashMap<u32, usize> = HashMap::new();
+    for event in doppelgamer::load_history()? {
+        match event {
+            HistoryEvent::Started {
+                pid,
+                name,
+                at,
+                minutes,
+            } => {
+                let planned = at
+                    .checked_add(jiff::Span::new().minutes(minutes))
+                    .unwrap_or(at);
+                open.insert(pid, sessions.len());
+                sessions.push((name, at, planned, None));
+            }
+            HistoryEvent::Ended { pid, at } => {
+                if let Some(index) = open.remove(&pid) {
+                    sessions[index].3 = Some(at);
+                }
+            }
+        }
+    }
+
+    let count = sessions.len();
+    let now = jiff::Timestamp::now();
+    let mut total = jiff::SignedDuration::ZERO;
+    let mut per_game: HashMap<String, (jiff::SignedDuration, usize)> = HashMap::new();
+    for (name, start, planned, end) in sessions {
+        // no end means the spoofer is still going or was killed, so assume it ran as planned
+        let end = end.unwrap_or_else(|| planned.min(now));
+        let played = end.duration_since(start).max(jiff::SignedDuration::ZERO);
+        total += played;
+        let entry = per_game.entry(name).or_default();
+        entry.0 += played;
+        entry.1 += 1;
+    }
+
+    println!("{count} sessions, {} total", format_played(total));
+    let mut per_game: Vec<_> = per_game.into_iter().collect();
+    per_game.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(&b.0)));
+    for (name, (played, sessions)) in per_game {
+        println!("{name}: {} over {sessions} sessions", format_played(played));
+    }
+    Ok(())
+}
+
+fn format_played(played: jiff::SignedDuration) -> String {
+    let round = jiff::SpanRound::new()
+        .largest(jiff::Unit::Hour)
+        .smallest(jiff::Unit::Minute);
+    jiff::Span::try_from(played)
+        .and_then(|span| span.round(round))
+        .map_or_else(
+            |_| format!("{}s", played.as_secs()),
+            |span| format!("{span:#}"),
+        )
+}
+
+fn format_remaining(quit_time: jiff::Timestamp) -> String {
+    let round = jiff::SpanRound::new()
+        .largest(jiff::Unit::Hour)
+        .smallest(jiff::Unit::Second);
+    match jiff::Timestamp::now()
+        .until(quit_time)
+        .and_then(|span| span.round(round))
+    {
+        Ok(remaining) if remaining.is_positive() => format!("{remaining:#} remaining"),
+        _ => "finishing up".to_string(),
+    }
+}
+
+/// Pairs each game with whether a spoofer for it is already running.
+async fn check_running(games: Vec<Game>) -> Vec<(Game, bool)> {
+    let fallback = games.iter().map(|game| (game.clone(), false)).collect();
     tokio::task::spawn_blocking(move || {
-        let Ok(exe_path) = game_exe_path(&game) else {
-            error!("Failed to determine spoofer path, aborting launch");
-            return;
-        };
-        let Ok(()) = fs::write(&exe_path, SPOOFER_BIN) else {
-            error!("Failed to write spoofer binary to disk, aborting launch");
-            return;
-        };
+        games
+            .into_iter()
+            .map(|game| {
+                let running = doppelgamer::data_dir()
+                    .and_then(|data_dir| exe_path_in(&data_dir, &game))
+                    .ok()
+                    .and_then(|path| find_running_spoofer(&path))
+                    .is_some();
+                (game, running)
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or(fallback)
+}
+
+fn stop_spoofers(name: Option<&str>) -> anyhow::Result<()> {
+    let (targets, others): (Vec<_>, Vec<_>) = load_tracked_spoofers()
+        .into_iter()
+        .filter(is_running)
+        .partition(|spoofer| name.is_none_or(|name| spoofer.name == name));
+
+    if targets.is_empty() {
+        match name {
+            Some(name) => anyhow::bail!("No running spoofer for {name:?}"),
+            None => println!("No running spoofers to stop"),
+        }
+    }
 
-        #[allow(clippy::zombie_processes)]
-        let Ok(_) = Command::new(&exe_path)
-            .arg(&game.name)
-            .arg(duration.to_string())
-            .creation_flags(0x0000_0008) // CREATE_NEW_CONSOLE
-            .spawn()
-        else {
-            error!("Failed to launch spoofer process, aborting launch");
-            return;
+    let mut remaining = others;
+    for spoofer in targets {
+        match stop_spoofer(&spoofer) {
+            Ok(()) => println!("Stopped {} (pid {})", spoofer.name, spoofer.pid),
+            Err(e) => {
+                error!(
+                    "Failed to stop {} (pid {}): {e:#}",
+                    spoofer.name, spoofer.pid
+                );
+                remaining.push(spoofer);
+            }
+        }
+    }
+    save_tracked_spoofers(&remaining)
+}
+
+/// Deletes copies of the spoofer binary from the data directory, skipping any that are running.
+fn clean_spoofers() -> anyhow::Result<()> {
+    let data_dir = doppelgamer::data_dir()?;
+    let running: HashSet<PathBuf> = load_tracked_spoofers()
+        .into_iter()
+        .filter(is_running)
+        .map(|spoofer| spoofer.exe)
+        .collect();
+
+    let copies = find_spoofer_copies(&data_dir, SPOOFER_BIN);
+
+    let mut removed = 0;
+    let mut reclaimed = 0;
+    for path in copies {
+        if running.contains(&path) {
+            info!("Keeping {}, it is still running", path.display());
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                removed += 1;
+                reclaimed += SPOOFER_BIN.len();
+                // tidy up the game's directories too, stopping at the first one with anything
+                // else in it
+                for dir in path.ancestors().skip(1) {
+                    if dir == data_dir || fs::remove_dir(dir).is_err() {
+                        break;
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to remove {}: {e}", path.display()),
+        }
+    }
+
+    println!("Removed {removed} spoofer copies, reclaiming {reclaimed} bytes");
+    Ok(())
+}
+
+/// Launches the embedded spoofer and remembers the launch for next time.
+fn start_spoofer(
+    game: &Game,
+    duration: u32,
+    options: &SpooferOptions,
+    replace: bool,
+) -> anyhow::Result<std::process::Child> {
+    let child =
+        spoofers::spawn_spoofer(game, duration, options, replace, SPOOFER_BIN).map_err(|e| {
+            if e.downcast_ref::<spoofers::SpooferNotWritten>().is_some() {
+                e.context(Failure::Write)
+            } else {
+                e
+            }
+        })?;
+    let last = LastLaunch {
+        name: game.name.clone(),
+        duration,
+    };
+    if let Err(e) = save_last_launch(&last) {
+        warn!("Failed to remember this launch: {e:#}");
+    }
+    Ok(child)
+}
+
+/// Spoofs each game in turn, starting the next one once the previous spoofer has exited.
+fn run_playlist(args: &Args, playlist: &Playlist) -> anyhow::Result<()> {
+    let mut games = load_games();
+    arrange_games(&mut games, args.tag.as_deref(), SortOrder::Unsorted);
+    let entries = playlist
+        .0
+        .iter()
+        .map(|(name, minutes)| {
+            let game = find_game(&games, name).context(Failure::InvalidSelection)?;
+            Ok((game, *minutes))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let total: u32 = entries.iter().map(|(_, minutes)| minutes).sum();
+    println!("Playlist, {total} minutes in total:");
+    for (game, minutes) in &entries {
+        println!(
+            "  {}: {minutes} minutes",
+            game.name
+                .if_supports_color(Stream::Stdout, |text| text.bold())
+        );
+    }
+    if args.dry_run {
+        for (game, minutes) in &entries {
+            print_dry_run(game, *minutes, &args.spoofer)?;
+        }
+        return Ok(());
+    }
+    // with nobody to ask, as from a hotkey, the command line already says what to play
+    if !args.yes && can_ask() && !confirm("Start the playlist?")? {
+        println!("Cancelled");
+        return Ok(());
+    }
+
+    for (number, (game, minutes)) in entries.iter().enumerate() {
+        if !args.quiet {
+            println!(
+                "{} {} for {minutes} minutes",
+                format!("[{}/{}]", number + 1, entries.len())
+                    .if_supports_color(Stream::Stdout, |text| text.dimmed()),
+                game.name
+                    .if_supports_color(Stream::Stdout, |text| text.bold())
+            );
+        }
+        let Some(replace) = ask_to_replace(game, args.force)? else {
+            println!("Left {} running, stopping the playlist", game.name);
+            return Ok(());
         };
+        let mut child = start_spoofer(game, *minutes, &args.spoofer, replace)?;
+        let status = child.wait().context("Failed to wait for the spoofer")?;
+        if !status.success() {
+            warn!("{} exited with {status}", game.name);
+        }
+    }
+    Ok(())
+}
+
+fn launch_spoofer(game: Game, duration: u32, options: SpooferOptions, replace: bool) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = start_spoofer(&game, duration, &options, replace) {
+            error!("{e:#}, aborting launch");
+        }
     });
 }
 
+fn game_names(games: &[Game]) -> String {
+    games
+        .iter()
+        .map(|game| game.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl App {
-    fn boot() -> (Self, iced::Task<Message>) {
-        let use_local = std::env::args().any(|arg| arg == "--local");
-        let games = load_games();
-        let app = Self {
-            query: String::new(),
+    fn boot(args: &Args, selected_games: Vec<Game>, query: String) -> (Self, iced::Task<Message>) {
+        let mut games = load_games();
+        arrange_games(&mut games, args.tag.as_deref(), args.sort);
+        // the game might have left the list since, in which case there's nothing to repeat
+        let last_launch = load_last_launch().and_then(|last| {
+            let game = games.iter().find(|game| game.name == last.name)?;
+            Some((game.clone(), validate_duration(last.duration).ok()?))
+        });
+        let mut app = Self {
+            query,
             games: games.iter().take(MAX_SHOWN_RESULTS).cloned().collect(),
-            selected_game: None,
-            duration: 15,
+            selected_games,
+            highlighted: None,
+            duration: args.minutes.unwrap_or(args.default_minutes),
+            pending_replace: None,
+            last_launch,
+            force: args.force,
+            dry_run: args.dry_run,
+            tag: args.tag.clone(),
+            sort: args.sort,
+            validate: args.validate || args.verbose,
+            problems: HashMap::new(),
+            spoofer_options: args.spoofer.clone(),
             matcher_tx: None,
-            initial_games: Some(games),
+            all_games: games,
+            games_urls: if !args.fetches() {
+                Vec::new()
+            } else {
+                args.games_urls()
+            },
+            retries: args.retries,
+            refresh: RefreshState::Idle,
             mode: iced::theme::Mode::None,
         };
+        if app.validate {
+            app.problems = exe_problems(&app.all_games);
+        }
         let mut tasks = vec![iced::system::theme().map(Message::SystemThemeChanged)];
-        if use_local {
-            info!("--local flag set, skipping remote fetch");
-        } else {
+        if args.fetches() {
             tasks.push(iced::Task::perform(
-                fetch_remote_games(),
+                fetch_remote_games(args.games_urls(), args.retries, false),
                 Message::GamesUpdated,
             ));
+        } else {
+            info!("Using the local games list without fetching");
         }
         (app, iced::Task::batch(tasks))
     }
 
-    fn update(&mut self, msg: Message) {
+    fn update(&mut self, msg: Message) -> iced::Task<Message> {
         match msg {
             Message::MatcherReady(tx) => {
-                self.matcher_tx = Some(tx);
-                if let Some(games) = self.initial_games.take()
-                    && let Some(tx) = &mut self.matcher_tx
-                {
-                    let _ = tx.try_send(MatcherCommand::ReloadGames(games));
+                let tx = self.matcher_tx.insert(tx);
+                let _ = tx.try_send(MatcherCommand::ReloadGames(self.all_games.clone()));
+                if !self.query.is_empty() {
+                    let _ = tx.try_send(MatcherCommand::Search(self.query.clone()));
                 }
             }
             Message::SystemThemeChanged(mode) => {
                 self.mode = mode;
             }
-            Message::GamesUpdated(Some(games)) => {
+            Message::GamesUpdated(Some(mut games)) => {
+                arrange_games(&mut games, self.tag.as_deref(), self.sort);
+                if self.validate {
+                    self.problems = exe_problems(&games);
+                }
                 self.games = games.iter().take(MAX_SHOWN_RESULTS).cloned().collect();
+                self.highlighted = None;
                 if let Some(tx) = &mut self.matcher_tx {
-                    let _ = tx.try_send(MatcherCommand::ReloadGames(games));
+                    let _ = tx.try_send(MatcherCommand::ReloadGames(games.clone()));
                 }
+                self.all_games = games;
             }
             Message::GamesUpdated(None) => {}
+            Message::Refresh => {
+                if self.refresh == RefreshState::Refreshing {
+                    return iced::Task::none();
+                }
+                self.refresh = RefreshState::Idle;
+                if self.games_urls.is_empty() {
+                    info!("Reloading the local games list");
+                    return self.update(Message::GamesUpdated(Some(load_games())));
+                }
+                info!("Refreshing the games list");
+                self.refresh = RefreshState::Refreshing;
+                return iced::Task::perform(
+                    fetch_remote_games(self.games_urls.clone(), self.retries, true),
+                    Message::Refreshed,
+                );
+            }
+            Message::Refreshed(games) => {
+                self.refresh = if games.is_some() {
+                    RefreshState::Idle
+                } else {
+                    warn!("Failed to refresh the games list, keeping the current one");
+                    RefreshState::Failed
+                };
+                return self.update(Message::GamesUpdated(games));
+            }
             Message::Search(query) => {
                 self.query = query;
                 if let Some(tx) = &mut self.matcher_tx {
                     let _ = tx.try_send(MatcherCommand::Search(self.query.clone()));
                 }
             }
+            Message::SubmitSearch => {
+                if let Some(game) = self.highlighted.and_then(|index| self.games.get(index)) {
+                    return self.update(Message::Select(game.clone()));
+                }
+                if self.query.is_empty()
+                    && let Some((game, duration)) = self.last_launch.clone()
+                {
+                    return self.update(Message::Launch(vec![game], duration));
+                }
+                // a query that narrows the list down to a single game is as good as clicking it
+                if let [game] = self.games.as_slice()
+                    && !self.selected_games.contains(game)
+                {
+                    self.selected_games.push(game.clone());
+                }
+            }
+            Message::HighlightNext => {
+                let last = self.games.len().saturating_sub(1);
+                self.highlighted = Some(self.highlighted.map_or(0, |index| (index + 1).min(last)));
+            }
+            Message::HighlightPrevious => {
+                // stepping up past the first result hands enter back to the search bar
+                self.highlighted = self.highlighted.and_then(|index| index.checked_sub(1));
+            }
             Message::Suggest(results) => {
                 self.games = results.into_iter().take(MAX_SHOWN_RESULTS).collect();
+                self.highlighted = None;
             }
             Message::Select(game) => {
-                self.selected_game = Some(game);
+                // clicking toggles, so several games can be launched together
+                if let Some(index) = self.selected_games.iter().position(|g| *g == game) {
+                    self.selected_games.remove(index);
+                } else {
+                    self.selected_games.push(game);
+                }
             }
             Message::SetDuration(duration) => {
                 self.duration = duration;
             }
-            Message::Launch(game, duration) => {
-                launch_spoofer(game, duration);
-                self.selected_game = None;
-                self.query.clear();
-                if let Some(tx) = &mut self.matcher_tx {
-                    let _ = tx.try_send(MatcherCommand::Search(String::new()));
+            Message::SetTray(tray) => {
+                self.spoofer_options.tray = tray;
+            }
+            Message::SetAlwaysOnTop(always_on_top) => {
+                self.spoofer_options.always_on_top = always_on_top;
+            }
+            Message::Launch(games, duration) => {
+                if self.dry_run {
+                    for game in &games {
+                        if let Err(e) = print_dry_run(game, duration, &self.spoofer_options) {
+                            error!("{e:#}");
+                        }
+                    }
+                    return iced::exit();
+                }
+                if self.force {
+                    self.start(games, duration, true);
+                } else {
+                    return iced::Task::perform(check_running(games), move |checked| {
+                        Message::LaunchChecked(checked, duration)
+                    });
                 }
             }
+            Message::LaunchChecked(checked, duration) => {
+                // start whatever isn't running yet and ask about the rest
+                let (running, idle): (Vec<_>, Vec<_>) =
+                    checked.into_iter().partition(|(_, running)| *running);
+                let running: Vec<Game> = running.into_iter().map(|(game, _)| game).collect();
+                if !idle.is_empty() {
+                    self.start(
+                        idle.into_iter().map(|(game, _)| game).collect(),
+                        duration,
+                        false,
+                    );
+                }
+                if !running.is_empty() {
+                    self.pending_replace = Some((running, duration));
+                }
+            }
+            Message::Replace => {
+                if let Some((games, duration)) = self.pending_replace.take() {
+                    self.start(games, duration, true);
+                }
+            }
+            Message::Random => {
+                if let Some(game) = self.all_games.choose(&mut rand::rng()) {
+                    info!("Picked {} at random", game.name);
+                    self.selected_games = vec![game.clone()];
+                }
+            }
+            Message::CancelReplace => {
+                self.pending_replace = None;
+            }
+        }
+        iced::Task::none()
+    }
+
+    fn start(&mut self, games: Vec<Game>, duration: u32, replace: bool) {
+        for game in games {
+            self.last_launch = Some((game.clone(), duration));
+            launch_spoofer(game, duration, self.spoofer_options.clone(), replace);
+        }
+        self.selected_games.clear();
+        self.query.clear();
+        if let Some(tx) = &mut self.matcher_tx {
+            let _ = tx.try_send(MatcherCommand::Search(String::new()));
         }
     }
 
     fn view(&self) -> iced::Element<'_, Message> {
-        let top: iced::Element<'_, Message> = self.selected_game.as_ref().map_or_else(
-            || iced::widget::text("Select a game below to get started").into(),
-            |game| {
-                iced::widget::row![
-                    iced::widget::text(format!("Launch {} for", &game.name)),
-                    iced_aw::widget::number_input(&self.duration, 1..=60, Message::SetDuration),
-                    iced::widget::text("minutes?"),
-                    iced::widget::space::horizontal(),
-                    iced::widget::button("Make it so!")
-                        .style(iced::widget::button::primary)
-                        .on_press(Message::Launch(game.clone(), self.duration)),
-                ]
-                .spacing(5)
-                .align_y(iced::Center)
-                .into()
+        let top: iced::Element<'_, Message> = if let Some((games, _)) = &self.pending_replace {
+            let prompt = if let [game] = games.as_slice() {
+                format!("{} is already running, replace it?", game.name)
+            } else {
+                format!("{} are already running, replace them?", game_names(games))
+            };
+            iced::widget::row![
+                iced::widget::text(prompt),
+                iced::widget::space::horizontal(),
+                iced::widget::button("Replace")
+                    .style(iced::widget::button::danger)
+                    .on_press(Message::Replace),
+                iced::widget::button("Cancel")
+                    .style(iced::widget::button::secondary)
+                    .on_press(Message::CancelReplace),
+            ]
+            .spacing(5)
+            .align_y(iced::Center)
+            .into()
+        } else if self.selected_games.is_empty() {
+            iced::widget::text("Select a game below to get started").into()
+        } else {
+            iced::widget::row![
+                iced::widget::text(format!("Launch {} for", game_names(&self.selected_games))),
+                iced_aw::widget::number_input(&self.duration, DURATION_RANGE, Message::SetDuration),
+                iced::widget::text("minutes?"),
+                iced::widget::space::horizontal(),
+                iced::widget::button("Make it so!")
+                    .style(iced::widget::button::primary)
+                    .on_press(Message::Launch(self.selected_games.clone(), self.duration)),
+            ]
+            .spacing(5)
+            .align_y(iced::Center)
+            .into()
+        };
+
+        let placeholder = self.last_launch.as_ref().map_or_else(
+            || "Search for a game...".to_string(),
+            |(game, duration)| {
+                format!(
+                    "Press Enter to repeat {} for {duration} minutes, or search for a game...",
+                    game.name
+                )
             },
         );
 
         iced::widget::column![
             top,
-            iced::widget::text_input("Search for a game...", &self.query).on_input(Message::Search),
+            iced::widget::toggler(self.spoofer_options.tray)
+                .label("Hide the spoofer in the tray when closed or minimized")
+                .on_toggle(Message::SetTray),
+            iced::widget::toggler(self.spoofer_options.always_on_top)
+                .label("Keep the spoofer above other windows")
+                .on_toggle(Message::SetAlwaysOnTop),
+            iced::widget::row![
+                iced::widget::text_input(&placeholder, &self.query)
+                    .on_input(Message::Search)
+                    .on_submit(Message::SubmitSearch),
+                iced::widget::button("Random")
+                    .style(iced::widget::button::secondary)
+                    .on_press(Message::Random),
+                iced::widget::button(match self.refresh {
+                    RefreshState::Idle => "Refresh",
+                    RefreshState::Refreshing => "Refreshing...",
+                    RefreshState::Failed => "Couldn't refresh, try again",
+                })
+                .style(iced::widget::button::secondary)
+                .on_press_maybe(
+                    (self.refresh != RefreshState::Refreshing).then_some(Message::Refresh)
+                ),
+            ]
+            .spacing(5),
             iced::widget::scrollable(
                 iced::widget::column![
-                    iced::widget::column(self.games.iter().map(|game| {
+                    iced::widget::column(self.games.iter().enumerate().map(|(index, game)| {
+                        let marker = if self.highlighted == Some(index) {
+                            "›"
+                        } else {
+                            " "
+                        };
                         iced::widget::button(
                             iced::widget::row![
+                                iced::widget::text(marker).width(10),
                                 iced::widget::text(&game.name),
                                 iced::widget::text(format!("({})", game.exe.to_string_lossy()))
                                     .wrapping(iced::widget::text::Wrapping::None)
-                                    .size(12)
+                                    .size(12),
+                                iced::widget::text(
+                                    self.problems
+                                        .get(&game.name)
+                                        .map_or_else(String::new, |problem| format!("⚠ {problem}"))
+                                )
+                                .wrapping(iced::widget::text::Wrapping::None)
+                                .size(12)
+                                .style(iced::widget::text::danger)
                             ]
                             .spacing(5)
                             .align_y(iced::Alignment::Center),
                         )
                         .width(iced::Fill)
                         .padding(0)
-                        .style(iced::widget::button::text)
+                        .style(if self.selected_games.contains(game) {
+                            iced::widget::button::secondary
+                        } else {
+                            iced::widget::button::text
+                        })
                         .on_press(Message::Select(game.clone()))
                         .into()
                     }))
@@ -316,6 +1683,18 @@ impl App {
         iced::Subscription::batch([
             iced::Subscription::run(Self::run_matcher),
             iced::system::theme_changes().map(Message::SystemThemeChanged),
+            iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                    key: iced::keyboard::Key::Named(named),
+                    ..
+                }) => match named {
+                    iced::keyboard::key::Named::ArrowDown => Some(Message::HighlightNext),
+                    iced::keyboard::key::Named::ArrowUp => Some(Message::HighlightPrevious),
+                    iced::keyboard::key::Named::F5 => Some(Message::Refresh),
+                    _ => None,
+                },
+                _ => None,
+            }),
         ])
     }
 
@@ -335,7 +1714,8 @@ impl App {
                 let injector = matcher.injector();
                 for game in games {
                     injector.push(game.clone(), |game, cols| {
-                        cols[0] = game.name.as_str().into();
+                        // aliases are matched along with the name, but the game is still one result
+                        cols[0] = game.names().collect::<Vec<_>>().join(" ").into();
                         cols[1] = game.exe.to_string_lossy().into();
                     });
                 }
@@ -378,7 +1758,7 @@ impl App {
                 matcher.pattern.reparse(
                     0,
                     &last_query,
-                    nucleo::pattern::CaseMatching::Smart,
+                    nucleo::pattern::CaseMatching::Ignore,
                     nucleo::pattern::Normalization::Smart,
                     false,
                 );
@@ -406,52 +1786,335 @@ impl App {
     }
 }
 
-fn cleanup_old_logs(log_dir: &std::path::Path) {
-    let Ok(entries) = fs::read_dir(log_dir) else {
-        return;
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
-            continue;
+fn main() -> std::process::ExitCode {
+    match run() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            // reported here and nowhere else, through the logs unless it failed before they
+            // were set up
+            if tracing::dispatcher::has_been_set() {
+                error!("{e:#}");
+            } else {
+                eprintln!("Error: {e:#}");
+            }
+            std::process::ExitCode::from(
+                e.downcast_ref::<Failure>()
+                    .map_or(1, |failure| failure.exit_code()),
+            )
+        }
+    }
+}
+
+/// Release builds are windows apps rather than console ones, so the launcher doesn't open with a
+/// console window, but that leaves them nowhere to print. Borrowing the console of whatever
+/// started us, like a terminal, gives one back. From a shortcut or hotkey there's none to
+/// borrow, which is fine since nobody would see the output anyway.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+    // SAFETY: this only attaches to the parent's console, and fails harmlessly if there isn't
+    // one or we already have our own
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+/// Everywhere else a console app is just a console app.
+#[cfg(not(windows))]
+const fn attach_console() {}
+
+fn run() -> anyhow::Result<()> {
+    // before anything is printed, --help and --version included
+    attach_console();
+    // arguments are parsed before logging is set up so that --quiet and --verbose can apply
+    let args = Args::try_parse();
+    let (quiet, verbose) = args
+        .as_ref()
+        .map_or((false, false), |args| (args.quiet, args.verbose));
+    if let Ok(args) = &args {
+        if args.portable {
+            let (config_dir, data_dir) = doppelgamer::portable_dirs()?;
+            doppelgamer::set_dirs(Some(config_dir), Some(data_dir))?;
+        } else {
+            doppelgamer::set_dirs(args.config_dir.clone(), args.data_dir.clone())?;
+        }
+    }
+    doppelgamer::init_logging("fuzz.log", quiet, verbose)?;
+
+    let mut args = args.unwrap_or_else(|e| e.exit());
+    args.apply_config(&doppelgamer::config::load_config());
+    // from here on --until is just another way of giving --minutes
+    if args.until.is_some() {
+        args.minutes = args.until;
+    }
+    if let Some(profile) = &args.profile {
+        games::set_profile(profile).context(Failure::InvalidSelection)?;
+    }
+
+    match &args.command {
+        Some(Subcommand::Status) => return print_status(),
+        Some(Subcommand::Stop { name, .. }) => {
+            return stop_spoofers(name.as_deref());
+        }
+        Some(Subcommand::AddGame {
+            name,
+            path,
+            exe,
+            tags,
+            aliases,
+            icon,
+            rpc_image,
+            overwrite,
+        }) => {
+            let game = Game {
+                name: name.clone(),
+                exe: path.join(exe),
+                tags: tags.clone(),
+                aliases: aliases.clone(),
+                icon: icon.clone(),
+                rpc_image: rpc_image.clone(),
+                local: true,
+            };
+            return add_game(game, *overwrite);
+        }
+        Some(Subcommand::RemoveGame { name, yes }) => {
+            return remove_game(name, *yes);
+        }
+        Some(Subcommand::Scan { dir, yes }) => {
+            return scan_games(dir, *yes);
+        }
+        Some(Subcommand::Stats) => return print_stats(),
+        Some(Subcommand::Clean) => return clean_spoofers(),
+        Some(Subcommand::Paths { open }) => {
+            return print_paths(*open);
+        }
+        Some(Subcommand::Validate { path }) => {
+            return validate_games_file(path.as_deref());
+        }
+        Some(Subcommand::Edit) => return edit_games(),
+        Some(Subcommand::Restore) => {
+            return restore_games();
+        }
+        Some(Subcommand::Export { path }) => {
+            return export_setup(path);
+        }
+        Some(Subcommand::Import { path, yes }) => {
+            return import_setup(path, *yes);
+        }
+        Some(Subcommand::Profile {
+            command: ProfileCommand::List,
+        }) => return print_profiles(),
+        None => {}
+    }
+
+    if let Some(playlist) = &args.playlist {
+        return run_playlist(&args, playlist);
+    }
+
+    if args.list {
+        let mut games = if args.fetches() {
+            games::get_games(false, args.games_urls(), args.retries)
+        } else {
+            info!("Using the local games list without fetching");
+            load_games()
         };
-        if !name.starts_with("fuzz.log.") {
-            continue;
+        arrange_games(&mut games, args.tag.as_deref(), args.sort);
+        // problems go to stderr so the list itself stays the same for scripts
+        if args.validate || args.verbose {
+            for game in &games {
+                if let Some(problem) = exe_problem(game) {
+                    warn!("{}: {problem}", game.name);
+                }
+            }
+        }
+        return print_games(&games, args.json);
+    }
+
+    // a game and duration without flags are --game and --minutes that don't ask first. either
+    // one that doesn't work out is left for the launcher to ask about instead of failing
+    let mut query = String::new();
+    if let Some(duration) = &args.duration {
+        match parse_minutes_arg(duration) {
+            Ok(minutes) => args.minutes = Some(minutes),
+            Err(e) => warn!("{e:#}, pick a duration in the launcher"),
+        }
+    }
+
+    // with --game the lookup happens up front so a bad name fails before any window opens.
+    // if --minutes was given too there is nothing left to ask, so skip the launcher entirely.
+    let selected_games = if let Some(name) = &args.game_name {
+        let mut games = load_games();
+        arrange_games(&mut games, args.tag.as_deref(), args.sort);
+        match find_listed_game(&games, name) {
+            Ok(game) => {
+                args.yes |= args.minutes.is_some();
+                vec![game]
+            }
+            Err(e) => {
+                warn!("{e:#}, pick one in the launcher");
+                if name.parse::<usize>().is_err() {
+                    query.clone_from(name);
+                }
+                Vec::new()
+            }
+        }
+    } else if args.random {
+        let mut games = load_games();
+        arrange_games(&mut games, args.tag.as_deref(), SortOrder::Unsorted);
+        let game = games
+            .choose(&mut rand::rng())
+            .cloned()
+            .context("There are no games to pick from")
+            .context(Failure::InvalidSelection)?;
+        if !args.quiet {
+            println!(
+                "Picked {}",
+                game.name
+                    .if_supports_color(Stream::Stdout, |text| text.bold())
+            );
         }
-        if let Ok(meta) = path.metadata()
-            && let Ok(modified) = meta.modified()
-            && let Ok(age) = modified.elapsed()
-            && age > std::time::Duration::from_secs(7 * 24 * 60 * 60)
+        vec![game]
+    } else if args.game.is_empty() {
+        Vec::new()
+    } else {
+        let mut games = load_games();
+        arrange_games(&mut games, args.tag.as_deref(), SortOrder::Unsorted);
+        args.game
+            .iter()
+            .map(|name| find_game(&games, name).context(Failure::InvalidSelection))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+    if let Some(minutes) = args.minutes
+        && !selected_games.is_empty()
+    {
+        if args.dry_run {
+            for game in &selected_games {
+                print_dry_run(game, minutes, &args.spoofer)?;
+            }
+            return Ok(());
+        }
+        // with nobody to ask, as from a shortcut, --game and --minutes already say what to launch
+        if !args.yes
+            && can_ask()
+            && !confirm(&launch_prompt(&selected_games, minutes, &args.spoofer))?
         {
-            let _ = fs::remove_file(&path);
+            println!("Cancelled");
+            return Ok(());
+        }
+        let mut failed = 0;
+        let mut not_written = false;
+        for game in &selected_games {
+            let Some(replace) = ask_to_replace(game, args.force)? else {
+                println!("Left {} running", game.name);
+                continue;
+            };
+            if let Err(e) = start_spoofer(game, minutes, &args.spoofer, replace) {
+                error!("{e:#}");
+                failed += 1;
+                not_written |= e.downcast_ref::<Failure>().is_some();
+            }
+        }
+        if failed > 0 {
+            let e = anyhow::anyhow!("{failed} of {} launches failed", selected_games.len());
+            return Err(if not_written {
+                e.context(Failure::Write)
+            } else {
+                e
+            });
         }
+        return Ok(());
     }
+
+    iced::application(
+        move || App::boot(&args, selected_games.clone(), query.clone()),
+        App::update,
+        App::view,
+    )
+    .window(iced::window::Settings {
+        min_size: Some(iced::Size::new(640.0, 640.0).ratio(1.6)),
+        icon: Some(
+            iced::window::icon::from_file_data(ICON, None).expect("embedded icon should be valid"),
+        ),
+        ..Default::default()
+    })
+    .title("Doppelgamer - Launcher")
+    .font(iced_aw::ICED_AW_FONT_BYTES)
+    .theme(App::theme)
+    .subscription(App::subscription)
+    .run()?;
+
+    Ok(())
 }
 
-fn main() -> anyhow::Result<()> {
-    let log_dir = etc_strategy()?.data_dir();
-    fs::create_dir_all(&log_dir)?;
-    cleanup_old_logs(&log_dir);
-    let file_appender = tracing_appender::rolling::daily(&log_dir, "fuzz.log");
-    tracing_subscriber::fmt()
-        .with_ansi(false)
-        .with_writer(std::io::stderr.and(file_appender))
-        .init();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    iced::application(App::boot, App::update, App::view)
-        .window(iced::window::Settings {
-            min_size: Some(iced::Size::new(640.0, 640.0).ratio(1.6)),
-            icon: Some(
-                iced::window::icon::from_file_data(ICON, None)
-                    .expect("embedded icon should be valid"),
-            ),
-            ..Default::default()
-        })
-        .title("Doppelgamer - Launcher")
-        .font(iced_aw::ICED_AW_FONT_BYTES)
-        .theme(App::theme)
-        .subscription(App::subscription)
-        .run()?;
+    /// Parses an end time this many minutes from now, written out to the second the way
+    /// someone might type it.
+    fn until_in(minutes: i64) -> anyhow::Result<u32> {
+        let end = jiff::Zoned::now().saturating_add(jiff::Span::new().minutes(minutes));
+        parse_until(&end.strftime("%Y-%m-%dT%H:%M:%S").to_string())
+    }
 
-    Ok(())
+    #[test]
+    fn until_counts_minutes_from_now() {
+        // losing the fraction of a second is made up for by rounding up
+        assert_eq!(until_in(90).expect("should parse"), 90);
+    }
+
+    #[test]
+    fn until_refuses_the_past() {
+        assert!(until_in(-5).is_err());
+        assert!(parse_until("2000-01-01T18:00").is_err());
+    }
+
+    #[test]
+    fn until_refuses_past_the_cap() {
+        let too_far = i64::from(doppelgamer::MAX_DURATION_MINUTES) + 5;
+        assert!(until_in(too_far).is_err());
+    }
+
+    #[test]
+    fn until_refuses_nonsense() {
+        for value in ["", "soon", "25:00", "18:00 tomorrow"] {
+            assert!(parse_until(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn playlists_split_into_games_and_minutes() {
+        let playlist = parse_playlist("Game A:30, Game B : 1h").expect("should parse");
+        assert_eq!(
+            playlist.0,
+            [("Game A".to_string(), 30), ("Game B".to_string(), 60)]
+        );
+    }
+
+    #[test]
+    fn playlist_names_can_have_colons() {
+        let playlist = parse_playlist("Re:Zero:20").expect("should parse");
+        assert_eq!(playlist.0, [("Re:Zero".to_string(), 20)]);
+    }
+
+    #[test]
+    fn bad_playlist_entries_are_refused() {
+        for value in ["Game A", ":30", "Game A:30,", "Game A:0", "Game A:soon"] {
+            assert!(parse_playlist(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn minute_ranges_stay_in_range() {
+        assert_eq!(parse_minutes_arg("30-30").expect("should parse"), 30);
+        for _ in 0..20 {
+            let minutes = parse_minutes_arg("30-1h").expect("should parse");
+            assert!((30..=60).contains(&minutes), "{minutes}");
+        }
+    }
+
+    #[test]
+    fn bad_minute_ranges_are_refused() {
+        for value in ["90-30", "0-30", "30-2000", "30-"] {
+            assert!(parse_minutes_arg(value).is_err(), "{value}");
+        }
+    }
 }
@@ -0,0 +1,602 @@
+//! Installing, launching and keeping track of spoofer processes.
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::{fs::PermissionsExt as _, process::CommandExt as _};
+#[cfg(windows)]
+use std::os::windows::process::CommandExt as _;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+
+use anyhow::Context as _;
+use tracing::{debug, info, warn};
+
+use crate::games::{Game, game_exe_path};
+use crate::{HistoryEvent, data_dir, write_atomically};
+
+/// A spoofer we launched, remembered in `spoofers.json` so later runs can find it.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct TrackedSpoofer {
+    pub pid: u32,
+    pub name: String,
+    pub exe: PathBuf,
+    pub quit_time: jiff::Timestamp,
+}
+
+/// Settings forwarded to the spoofer on its command line.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct SpooferOptions {
+    /// Hide the spoofer in the system tray instead of closing or minimizing it.
+    #[arg(long)]
+    pub tray: bool,
+    /// Keep the spoofer window above other windows.
+    #[arg(long)]
+    pub always_on_top: bool,
+    /// Show a desktop notification when the session is over.
+    #[arg(long)]
+    pub notify: bool,
+    /// Notify at these points in the session, as percentages gone by or time left, like 50,1m.
+    #[arg(long, value_name = "ALERTS", value_parser = crate::parse_alerts)]
+    pub alerts: Option<crate::Alerts>,
+    /// Flash the spoofer on the taskbar once this many seconds are left, or never if 0.
+    #[arg(long, value_name = "SECS")]
+    pub flash_secs: Option<u32>,
+    /// Run the spoofer without a window, just waiting out the session. Ctrl+C ends it early.
+    #[arg(long)]
+    pub headless: bool,
+    /// Start the spoofer without a console window. Windows only. With `--headless` too, nothing
+    /// shows up at all.
+    #[arg(long)]
+    pub no_console: bool,
+    /// Start the session over when it ends instead of exiting, until stopped by hand.
+    #[arg(long = "loop")]
+    pub loop_session: bool,
+    /// Carry on with the session a crashed spoofer for the game was in the middle of, if any.
+    #[arg(long)]
+    pub resume: bool,
+    /// Keep the computer from going to sleep while the spoofer runs. Windows only.
+    #[arg(long)]
+    pub keep_awake: bool,
+    /// Let the spoofer window be resized.
+    #[arg(long)]
+    pub resizable: bool,
+    /// Start the spoofer window minimized, so it doesn't cover whatever you're doing.
+    #[arg(long)]
+    pub minimized: bool,
+    /// Initial size of the spoofer window, such as 480x240.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = crate::parse_window_size)]
+    pub window_size: Option<(u16, u16)>,
+    /// Draw the spoofer window bigger or smaller, such as 1.5 on a high resolution screen.
+    #[arg(long, value_name = "FACTOR", value_parser = crate::parse_scale)]
+    pub scale: Option<f32>,
+    /// Light or dark spoofer window. Follows the system unless set.
+    #[arg(long, value_enum)]
+    pub theme: Option<crate::ThemeChoice>,
+    /// Show the spoofer's countdown as a clock, or spelled out in units.
+    #[arg(long, value_enum)]
+    pub countdown: Option<crate::CountdownStyle>,
+    /// How the spoofer window is laid out.
+    #[arg(long, value_enum)]
+    pub skin: Option<crate::SpooferSkin>,
+    /// Update the spoofer every this many seconds instead of every second, to wake up less.
+    #[arg(long = "refresh", value_name = "SECS", value_parser = crate::parse_refresh_secs)]
+    pub refresh_secs: Option<u32>,
+    /// strftime format for the spoofer's exit time, such as "%I:%M %p".
+    #[arg(long, value_name = "FORMAT", value_parser = crate::parse_time_format)]
+    pub time_format: Option<String>,
+    /// A line for the spoofer window to show under the game, like "Grinding raids".
+    #[arg(long, value_name = "TEXT")]
+    pub status: Option<String>,
+    /// Show the session as Discord Rich Presence too, using this Discord application id.
+    #[arg(long, value_name = "APP_ID")]
+    pub rpc: Option<String>,
+    /// How long the spoofer waits after the duration is up before exiting.
+    #[arg(long = "grace", value_name = "SECS")]
+    pub grace_secs: Option<u32>,
+}
+
+impl SpooferOptions {
+    fn apply(&self, command: &mut Command) {
+        if self.tray {
+            command.arg("--tray");
+        }
+        if self.always_on_top {
+            command.arg("--always-on-top");
+        }
+        if self.notify {
+            command.arg("--notify");
+        }
+        if let Some(alerts) = &self.alerts {
+            command.arg("--alerts").arg(alerts.to_string());
+        }
+        if let Some(flash_secs) = self.flash_secs {
+            command.arg("--flash-secs").arg(flash_secs.to_string());
+        }
+        if self.headless {
+            command.arg("--headless");
+        }
+        if self.loop_session {
+            command.arg("--loop");
+        }
+        if self.resume {
+            command.arg("--resume");
+        }
+        if self.keep_awake {
+            command.arg("--keep-awake");
+        }
+        if self.resizable {
+            command.arg("--resizable");
+        }
+        if self.minimized {
+            command.arg("--minimized");
+        }
+        if let Some(scale) = self.scale {
+            command.arg("--scale").arg(scale.to_string());
+        }
+        if let Some((width, height)) = self.window_size {
+            command
+                .arg("--window-size")
+                .arg(format!("{width}x{height}"));
+        }
+        if let Some(theme) = self
+            .theme
+            .and_then(|theme| clap::ValueEnum::to_possible_value(&theme))
+        {
+            command.arg("--theme").arg(theme.get_name());
+        }
+        if let Some(countdown) = self
+            .countdown
+            .and_then(|countdown| clap::ValueEnum::to_possible_value(&countdown))
+        {
+            command.arg("--countdown").arg(countdown.get_name());
+        }
+        if let Some(skin) = self
+            .skin
+            .and_then(|skin| clap::ValueEnum::to_possible_value(&skin))
+        {
+            command.arg("--skin").arg(skin.get_name());
+        }
+        if let Some(refresh_secs) = self.refresh_secs {
+            command.arg("--refresh").arg(refresh_secs.to_string());
+        }
+        if let Some(time_format) = &self.time_format {
+            command.arg("--time-format").arg(time_format);
+        }
+        if let Some(status) = &self.status {
+            command.arg("--status").arg(status);
+        }
+        if let Some(app_id) = &self.rpc {
+            command.arg("--rpc").arg(app_id);
+        }
+        if let Some(grace_secs) = self.grace_secs {
+            command.arg("--grace").arg(grace_secs.to_string());
+        }
+    }
+
+    /// The grace period the spoofer will end up using, which it may also pick up from the
+    /// environment it inherits from us.
+    #[must_use]
+    pub fn grace_secs(&self) -> u32 {
+        self.grace_secs
+            .or_else(crate::grace_secs_from_env)
+            .unwrap_or(crate::DEFAULT_GRACE_SECS)
+    }
+
+    /// When a spoofer started now for `duration` minutes will exit, grace period included.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the quit time would be out of range.
+    pub fn quit_time(&self, duration: u32) -> anyhow::Result<jiff::Timestamp> {
+        jiff::Timestamp::now()
+            .checked_add(
+                jiff::Span::new()
+                    .minutes(duration)
+                    .seconds(self.grace_secs()),
+            )
+            .context("Failed to compute quit time")
+    }
+}
+
+fn spoofers_json_path() -> anyhow::Result<PathBuf> {
+    let mut path = data_dir()?;
+    fs::create_dir_all(&path)?;
+    path.push("spoofers.json");
+    Ok(path)
+}
+
+/// Reads the spoofers launched so far. A missing or unreadable file just means nothing is
+/// being tracked.
+#[must_use]
+pub fn load_tracked_spoofers() -> Vec<TrackedSpoofer> {
+    if let Ok(path) = spoofers_json_path()
+        && let Ok(data) = fs::read_to_string(&path)
+    {
+        match serde_json::from_str(&data) {
+            Ok(spoofers) => return spoofers,
+            Err(e) => warn!("Ignoring corrupt spoofers.json: {e}"),
+        }
+    }
+    Vec::new()
+}
+
+/// Replaces the tracked spoofers.
+///
+/// # Errors
+///
+/// Fails if `spoofers.json` can't be written.
+pub fn save_tracked_spoofers(spoofers: &[TrackedSpoofer]) -> anyhow::Result<()> {
+    write_atomically(&spoofers_json_path()?, |file| {
+        Ok(serde_json::to_writer(file, spoofers)?)
+    })
+}
+
+fn track_spoofer(spoofer: TrackedSpoofer) -> anyhow::Result<()> {
+    let mut spoofers = load_tracked_spoofers();
+    spoofers.retain(is_running);
+    spoofers.push(spoofer);
+    save_tracked_spoofers(&spoofers)
+}
+
+/// Checks the process is still alive and still the one we launched, since windows is quick to
+/// hand out a dead spoofer's pid to something else.
+#[cfg(windows)]
+#[must_use]
+pub fn is_running(spoofer: &TrackedSpoofer) -> bool {
+    let Some(exe_name) = spoofer.exe.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let Ok(output) = Command::new("tasklist")
+        .args([
+            "/FI",
+            &format!("PID eq {}", spoofer.pid),
+            "/FO",
+            "CSV",
+            "/NH",
+        ])
+        .creation_flags(0x0800_0000) // CREATE_NO_WINDOW
+        .output()
+    else {
+        return false;
+    };
+    // matching rows look like "overwatch.exe","1234",... and anything else is an info message
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix('"')?.split('"').next())
+        .any(|image| image.eq_ignore_ascii_case(exe_name))
+}
+
+/// Checks the process is still alive and still running the exe we launched.
+#[cfg(unix)]
+#[must_use]
+pub fn is_running(spoofer: &TrackedSpoofer) -> bool {
+    fs::read_link(format!("/proc/{}/exe", spoofer.pid)).is_ok_and(|exe| exe == spoofer.exe)
+}
+
+/// The named pipe a spoofer answers [`query_status`] on, one per process.
+#[must_use]
+pub fn status_pipe_name(pid: u32) -> String {
+    format!(r"\\.\pipe\doppelgamer-spoofer-{pid}")
+}
+
+/// Sends one line to the spoofer with `pid` and returns the line it answers with.
+#[cfg(windows)]
+fn ask_spoofer(pid: u32, request: &str) -> anyhow::Result<String> {
+    use std::io::{BufRead as _, BufReader, Write as _};
+    let mut pipe = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(status_pipe_name(pid))?;
+    pipe.write_all(format!("{request}\n").as_bytes())?;
+    let mut answer = String::new();
+    BufReader::new(pipe).read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Named pipes are a windows thing, so elsewhere [`is_running`] is all there is.
+#[cfg(not(windows))]
+fn ask_spoofer(_pid: u32, _request: &str) -> anyhow::Result<String> {
+    anyhow::bail!("status pipes are only supported on windows")
+}
+
+/// Asks the spoofer with `pid` how many seconds it has left. Only a live spoofer can answer,
+/// so this doubles as a quicker and more exact check than [`is_running`].
+///
+/// # Errors
+///
+/// Fails if nothing is listening on the spoofer's pipe or the answer makes no sense.
+pub fn query_status(pid: u32) -> anyhow::Result<u32> {
+    let answer = ask_spoofer(pid, "status")?;
+    answer
+        .parse()
+        .with_context(|| format!("Unexpected status {answer:?}"))
+}
+
+/// Asks a spoofer to end its session the same way its "stop now" button does. That works
+/// however it's running, where closing the window does nothing to a spoofer in the tray or one
+/// without a window at all.
+///
+/// # Errors
+///
+/// Fails if the spoofer can't be reached, or can't be signalled on platforms without the
+/// status pipe.
+pub fn terminate_spoofer(spoofer: &TrackedSpoofer) -> anyhow::Result<()> {
+    match ask_spoofer(spoofer.pid, "stop") {
+        Ok(answer) if answer == "stopping" => return Ok(()),
+        Ok(answer) => debug!("Unexpected answer to stop: {answer:?}"),
+        Err(e) => debug!("Couldn't ask the spoofer to stop: {e:#}"),
+    }
+    signal_spoofer(spoofer, false)
+}
+
+/// Ends a spoofer that didn't stop when asked. It can't record the end of its session itself,
+/// so that's done here instead.
+///
+/// # Errors
+///
+/// Fails if the process couldn't be killed, for example because it already exited.
+pub fn kill_spoofer(spoofer: &TrackedSpoofer) -> anyhow::Result<()> {
+    warn!("Killing {} (pid {})", spoofer.name, spoofer.pid);
+    signal_spoofer(spoofer, true)?;
+    let ended = HistoryEvent::Ended {
+        pid: spoofer.pid,
+        at: jiff::Timestamp::now(),
+    };
+    if let Err(e) = crate::append_history(&ended) {
+        warn!("Failed to record the end of this session: {e:#}");
+    }
+    match fs::remove_file(session_path(&spoofer.exe)) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove the saved session: {e}"),
+    }
+    Ok(())
+}
+
+/// Where the spoofer running from `exe` keeps its session for `--resume`, so every game gets
+/// its own.
+#[must_use]
+pub fn session_path(exe: &Path) -> PathBuf {
+    let mut path = exe.as_os_str().to_owned();
+    path.push(".session.json");
+    path.into()
+}
+
+/// Asks the process to close, or makes it with `force`. Without /F taskkill only asks the
+/// window to close, the same as clicking the close button, which is all an older spoofer
+/// without a stop command understands.
+#[cfg(windows)]
+fn signal_spoofer(spoofer: &TrackedSpoofer, force: bool) -> anyhow::Result<()> {
+    let mut command = Command::new("taskkill");
+    command.args(["/PID", &spoofer.pid.to_string()]);
+    if force {
+        command.arg("/F");
+    }
+    let status = command
+        .creation_flags(0x0800_0000) // CREATE_NO_WINDOW
+        .status()
+        .context("Failed to run taskkill")?;
+    anyhow::ensure!(status.success(), "taskkill exited with {status}");
+    Ok(())
+}
+
+/// Sends SIGTERM, or SIGKILL with `force`.
+#[cfg(unix)]
+fn signal_spoofer(spoofer: &TrackedSpoofer, force: bool) -> anyhow::Result<()> {
+    let mut command = Command::new("kill");
+    if force {
+        command.arg("-KILL");
+    }
+    let status = command
+        .arg(spoofer.pid.to_string())
+        .status()
+        .context("Failed to run kill")?;
+    anyhow::ensure!(status.success(), "kill exited with {status}");
+    Ok(())
+}
+
+/// Finds the running spoofer using the given exe, if there is one.
+#[must_use]
+pub fn find_running_spoofer(exe_path: &Path) -> Option<TrackedSpoofer> {
+    load_tracked_spoofers()
+        .into_iter()
+        .find(|spoofer| spoofer.exe == exe_path && is_running(spoofer))
+}
+
+/// Stops a spoofer and waits for it to exit, so its exe can be reused. One that's still
+/// running after five seconds is killed.
+///
+/// # Errors
+///
+/// Fails if the spoofer can't be stopped or killed, or is somehow still running after that.
+#[tracing::instrument(skip_all, fields(game = %spoofer.name, pid = spoofer.pid))]
+pub fn stop_spoofer(spoofer: &TrackedSpoofer) -> anyhow::Result<()> {
+    info!("Stopping running spoofer");
+    if let Err(e) = terminate_spoofer(spoofer) {
+        warn!("Failed to ask the spoofer to stop: {e:#}");
+    } else if wait_for_exit(spoofer) {
+        return Ok(());
+    }
+    kill_spoofer(spoofer)?;
+    anyhow::ensure!(
+        wait_for_exit(spoofer),
+        "{} (pid {}) did not exit in time",
+        spoofer.name,
+        spoofer.pid
+    );
+    Ok(())
+}
+
+/// Gives a spoofer five seconds to exit, since the process holds its exe open until it has
+/// fully gone.
+fn wait_for_exit(spoofer: &TrackedSpoofer) -> bool {
+    for _ in 0..20 {
+        if !is_running(spoofer) {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+    !is_running(spoofer)
+}
+
+/// Checks whether `path` already holds exactly the given spoofer binary.
+#[must_use]
+pub fn is_spoofer_copy(path: &Path, spoofer_bin: &[u8]) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.len() == spoofer_bin.len() as u64)
+        && fs::read(path).is_ok_and(|data| data == spoofer_bin)
+}
+
+/// Attached to launch errors from writing the spoofer copy, so they can be told apart from the
+/// spoofer failing to start.
+#[derive(Clone, Copy, Debug)]
+pub struct SpooferNotWritten;
+
+impl std::fmt::Display for SpooferNotWritten {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Failed to write spoofer binary to disk")
+    }
+}
+
+fn install_spoofer(exe_path: &Path, spoofer_bin: &[u8]) -> anyhow::Result<()> {
+    // an up to date copy may well be in use by another spoofer, so leave it be
+    if is_spoofer_copy(exe_path, spoofer_bin) {
+        info!(
+            "Spoofer binary already up to date at {}",
+            exe_path.display()
+        );
+        return Ok(());
+    }
+    fs::write(exe_path, spoofer_bin).context(SpooferNotWritten)?;
+    #[cfg(unix)]
+    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755))
+        .context("Failed to make spoofer binary executable")
+        .context(SpooferNotWritten)?;
+    Ok(())
+}
+
+/// Walks `dir` for copies of the given spoofer binary.
+#[must_use]
+pub fn find_spoofer_copies(dir: &Path, spoofer_bin: &[u8]) -> Vec<PathBuf> {
+    let mut copies = Vec::new();
+    collect_spoofer_copies(dir, spoofer_bin, &mut copies);
+    copies
+}
+
+fn collect_spoofer_copies(dir: &Path, spoofer_bin: &[u8], copies: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_spoofer_copies(&path, spoofer_bin, copies);
+        } else if file_type.is_file() && is_spoofer_copy(&path, spoofer_bin) {
+            copies.push(path);
+        }
+    }
+}
+
+/// Builds the command that starts the spoofer installed at `exe_path` for `duration` minutes.
+#[must_use]
+pub fn spoofer_command(
+    exe_path: &Path,
+    game: &Game,
+    duration: u32,
+    options: &SpooferOptions,
+) -> Command {
+    let mut command = Command::new(exe_path);
+    options.apply(&mut command);
+    for (var, dir) in crate::dir_overrides() {
+        if let Some(dir) = dir {
+            command.env(var, dir);
+        }
+    }
+    if let Some(icon) = game.icon_path() {
+        command.arg("--icon").arg(icon);
+    }
+    if let Some(image) = game.rpc_image.as_ref().filter(|_| options.rpc.is_some()) {
+        command.arg("--rpc-image").arg(image);
+    }
+    // game names can start with a dash, so keep them from being read as flags
+    command.arg("--").arg(&game.name).arg(duration.to_string());
+
+    #[cfg(windows)]
+    command.creation_flags(if options.no_console {
+        0x0800_0000 // CREATE_NO_WINDOW
+    } else {
+        0x0000_0008 // CREATE_NEW_CONSOLE
+    });
+    #[cfg(unix)]
+    command.process_group(0); // keep it alive if the launcher's terminal goes away
+    command
+}
+
+/// Copies `spoofer_bin` to the game's exe path and starts it for `duration` minutes, recording
+/// it in the tracked spoofers and the history. A spoofer already running for the game is
+/// closed first if `replace` is set.
+///
+/// # Errors
+///
+/// Fails if the game's exe path can't be used, a spoofer is already running for it and
+/// `replace` isn't set, or the spoofer can't be written or started.
+#[tracing::instrument(skip_all, fields(game = %game.name, minutes = duration))]
+pub fn spawn_spoofer(
+    game: &Game,
+    duration: u32,
+    options: &SpooferOptions,
+    replace: bool,
+    spoofer_bin: &[u8],
+) -> anyhow::Result<Child> {
+    info!("Launching spoofer");
+    anyhow::ensure!(
+        !spoofer_bin.is_empty(),
+        "The spoofer binary is empty, so there is nothing to launch"
+    );
+
+    let quit_time = options.quit_time(duration)?;
+    anyhow::ensure!(
+        quit_time > jiff::Timestamp::now(),
+        "The session would already be over at {quit_time}, check the system clock"
+    );
+    let exe_path = game_exe_path(game).context("Failed to determine spoofer path")?;
+    if let Some(existing) = find_running_spoofer(&exe_path) {
+        anyhow::ensure!(
+            replace,
+            "{} is already running (pid {}), use --force to replace it",
+            existing.name,
+            existing.pid
+        );
+        stop_spoofer(&existing).context("Failed to stop the running spoofer")?;
+    }
+    install_spoofer(&exe_path, spoofer_bin)?;
+
+    let mut command = spoofer_command(&exe_path, game, duration, options);
+    let child = command
+        .spawn()
+        .context("Failed to launch spoofer process")?;
+    info!(pid = child.id(), "Started spoofer");
+    debug!(?command, "Spoofer command line");
+
+    let spoofer = TrackedSpoofer {
+        pid: child.id(),
+        name: game.name.clone(),
+        exe: exe_path,
+        quit_time,
+    };
+    let started = HistoryEvent::Started {
+        pid: spoofer.pid,
+        name: game.name.clone(),
+        at: jiff::Timestamp::now(),
+        minutes: duration,
+    };
+    if let Err(e) = crate::append_history(&started) {
+        warn!("Failed to record this session in the history: {e:#}");
+    }
+    if let Err(e) = track_spoofer(spoofer) {
+        warn!("Failed to record launched spoofer: {e:#}");
+    }
+
+    Ok(child)
+}